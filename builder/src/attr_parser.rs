@@ -0,0 +1,176 @@
+use proc_macro2::{Span, TokenStream};
+use syn::{
+    ext::IdentExt,
+    parse::{Parse, ParseStream, Parser},
+    punctuated::Punctuated,
+    spanned::Spanned,
+    Lit, LitStr, Token, Type,
+};
+
+use crate::errors::Errors;
+
+/// A single `key`, `key = value` or `key(...)` entry inside `#[builder(...)]`.
+pub(crate) struct AttrItem {
+    pub(crate) key: syn::Ident,
+    pub(crate) value: AttrValue,
+}
+
+pub(crate) enum AttrValue {
+    Flag,
+    Lit(Lit),
+    Type(Box<Type>),
+    List(TokenStream),
+}
+
+impl AttrItem {
+    pub(crate) fn span(&self) -> Span {
+        match &self.value {
+            AttrValue::Flag => self.key.span(),
+            AttrValue::Lit(lit) => lit.span(),
+            AttrValue::Type(ty) => ty.span(),
+            AttrValue::List(tokens) => tokens.span(),
+        }
+    }
+
+    pub(crate) fn expect_flag(&self) -> syn::Result<()> {
+        match &self.value {
+            AttrValue::Flag => Ok(()),
+            _ => Err(syn::Error::new(
+                self.span(),
+                format!("expected `{}` to not take a value", self.key),
+            )),
+        }
+    }
+
+    /// The error for a key that is not one of `accepted`, listing all of them.
+    pub(crate) fn unknown_key(&self, accepted: &[&str]) -> syn::Error {
+        let accepted = accepted
+            .iter()
+            .map(|key| format!("`{}`", key))
+            .collect::<Vec<_>>()
+            .join(", ");
+        syn::Error::new(
+            self.key.span(),
+            format!(
+                "unknown builder attribute `{}`, expected one of {}",
+                self.key, accepted
+            ),
+        )
+    }
+
+    /// `key` on its own means `true`, otherwise `key = true` or `key = false`.
+    pub(crate) fn expect_bool(&self) -> syn::Result<bool> {
+        match &self.value {
+            AttrValue::Flag => Ok(true),
+            AttrValue::Lit(Lit::Bool(lit_bool)) => Ok(lit_bool.value),
+            _ => Err(syn::Error::new(
+                self.span(),
+                format!("expected `{0}`, `{0} = true` or `{0} = false`", self.key),
+            )),
+        }
+    }
+
+    pub(crate) fn expect_str(&self) -> syn::Result<&LitStr> {
+        match &self.value {
+            AttrValue::Lit(Lit::Str(lit_str)) => Ok(lit_str),
+            _ => Err(syn::Error::new(
+                self.span(),
+                format!("expected `{} = \"...\"`", self.key),
+            )),
+        }
+    }
+
+    /// `key = "name"` naming a generated item. Keywords are taken as raw
+    /// identifiers, so `each = "type"` gives a setter called `r#type`.
+    pub(crate) fn expect_ident(&self) -> syn::Result<syn::Ident> {
+        let lit_str = self.expect_str()?;
+        let value = lit_str.value();
+        let invalid = || {
+            syn::Error::new(
+                lit_str.span(),
+                format!("`{}` is not a valid identifier", value),
+            )
+        };
+        let ident = syn::Ident::parse_any
+            .parse_str(&value)
+            .map_err(|_| invalid())?;
+        let unraw = ident.unraw().to_string();
+        if ["_", "self", "Self", "super", "crate"].contains(&unraw.as_str()) {
+            return Err(invalid());
+        }
+        if syn::parse_str::<syn::Ident>(&unraw).is_ok() {
+            Ok(syn::Ident::new(&unraw, lit_str.span()))
+        } else {
+            Ok(syn::Ident::new_raw(&unraw, lit_str.span()))
+        }
+    }
+
+    /// `key = path::to::item`, or the same path as a string.
+    pub(crate) fn expect_path(&self) -> syn::Result<syn::Path> {
+        match &self.value {
+            AttrValue::Type(ty) => match ty.as_ref() {
+                Type::Path(type_path) if type_path.qself.is_none() => Ok(type_path.path.clone()),
+                _ => Err(syn::Error::new(self.span(), "expected a path")),
+            },
+            AttrValue::Lit(Lit::Str(lit_str)) => lit_str.parse(),
+            _ => Err(syn::Error::new(
+                self.span(),
+                format!("expected `{} = path::to::function`", self.key),
+            )),
+        }
+    }
+
+    /// The entries of a `key(...)` item.
+    pub(crate) fn expect_list(&self) -> syn::Result<Vec<AttrItem>> {
+        self.expect_list_of()
+    }
+
+    /// The comma separated entries of a `key(...)` item, parsed as `T`.
+    pub(crate) fn expect_list_of<T: Parse>(&self) -> syn::Result<Vec<T>> {
+        match &self.value {
+            AttrValue::List(tokens) => Ok(Punctuated::<T, Token![,]>::parse_terminated
+                .parse2(tokens.clone())?
+                .into_iter()
+                .collect()),
+            _ => Err(syn::Error::new(
+                self.span(),
+                format!("expected `{}(...)`", self.key),
+            )),
+        }
+    }
+}
+
+impl Parse for AttrItem {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        let key = syn::Ident::parse_any(input)?;
+        let value = if input.peek(Token![=]) {
+            input.parse::<Token![=]>()?;
+            if input.peek(Lit) {
+                AttrValue::Lit(input.parse()?)
+            } else {
+                AttrValue::Type(input.parse()?)
+            }
+        } else if input.peek(syn::token::Paren) {
+            let content;
+            syn::parenthesized!(content in input);
+            AttrValue::List(content.parse()?)
+        } else {
+            AttrValue::Flag
+        };
+        Ok(Self { key, value })
+    }
+}
+
+/// Parses every `#[builder(...)]` attribute in `attrs`, ignoring all others.
+/// Attributes that fail to parse are pushed onto `errors` and skipped.
+pub(crate) fn parse_builder_attrs(attrs: &[syn::Attribute], errors: &mut Errors) -> Vec<AttrItem> {
+    let mut items = vec![];
+    for attr in attrs.iter().filter(|attr| attr.path.is_ident("builder")) {
+        if let Some(parsed) =
+            errors.record(attr.parse_args_with(Punctuated::<AttrItem, Token![,]>::parse_terminated))
+        {
+            items.extend(parsed);
+        }
+    }
+    items
+}
@@ -1,70 +1,208 @@
-use syn::{spanned::Spanned, Lit, Meta, NestedMeta};
+use proc_macro2::{Span, TokenStream};
+use quote::{quote, ToTokens};
+use syn::Lit;
 
+use crate::{
+    attr_parser::{parse_builder_attrs, AttrItem, AttrValue},
+    errors::Errors,
+};
+
+const KEYS: &[&str] = &[
+    "each",
+    "item",
+    "default",
+    "setter",
+    "name",
+    "optional",
+    "repeated",
+    "merge",
+    "env",
+    "skip",
+    "compute",
+    "sub_builder",
+    "try_setter",
+    "parse_with",
+    "group",
+    "exactly_one",
+    "at_least_one",
+    "at_most_one",
+];
+const SETTER_KEYS: &[&str] = &["into", "strip_option", "name"];
+
+/// Options given through `#[builder(...)]` on a field.
+#[derive(Default)]
 pub(crate) struct BuilderAttr {
-    #[allow(unused)]
-    pub(crate) name_ident: syn::Ident,
-    pub(crate) value_ident: syn::Ident,
-    #[allow(unused)]
-    pub(crate) span: proc_macro2::Span,
+    pub(crate) each: Option<syn::Ident>,
+    pub(crate) default: Option<FieldDefault>,
+    pub(crate) setter: SetterAttr,
+    pub(crate) name: Option<syn::Ident>,
+    pub(crate) item: Option<syn::Type>,
+    pub(crate) optional: bool,
+    pub(crate) repeated: bool,
+    /// How a collection is merged, and where that was given.
+    pub(crate) merge: Option<(Merge, Span)>,
+    /// The environment variable `fill_from_env` reads the field from.
+    pub(crate) env: Option<syn::LitStr>,
+    /// `#[builder(skip)]`, i.e. no setter and `Default::default()` in `build()`.
+    pub(crate) skip: bool,
+    /// `#[builder(compute = "...")]`, evaluated in `build()` instead of a setter.
+    pub(crate) compute: Option<Box<syn::Expr>>,
+    /// `#[builder(sub_builder)]`, i.e. the field is built by its own builder.
+    pub(crate) sub_builder: bool,
+    /// `#[builder(try_setter)]`, i.e. a `try_<setter>` taking `impl TryInto`.
+    pub(crate) try_setter: bool,
+    /// The `fn(&str) -> Result<T, E>` behind the `parse_<setter>` setter.
+    pub(crate) parse_with: Option<syn::Path>,
+    /// `#[builder(group = "...")]`, the group of fields this one belongs to.
+    pub(crate) group: Option<syn::Ident>,
+    /// How many fields of the group may be set, and where that was given.
+    pub(crate) group_rule: Option<(GroupRule, Span)>,
 }
 
-impl<'a> TryFrom<&'a syn::Attribute> for BuilderAttr {
-    type Error = syn::Error;
-    fn try_from(value: &'a syn::Attribute) -> Result<Self, Self::Error> {
-        let outer_meta = value.parse_meta()?;
-        if let Meta::List(list) = &outer_meta {
-            let inner = list.nested.iter().next().ok_or_else(|| {
-                syn::Error::new(list.span(), "expected attribute to not be empty")
-            })?;
-            if let NestedMeta::Meta(meta) = inner {
-                if let Meta::NameValue(named_value) = meta {
-                    let name_ident = match named_value.path.get_ident() {
-                        Some(ident) => {
-                            if ident.to_string() != "each" {
-                                return Err(syn::Error::new(
-                                    named_value.span(),
-                                    "expected `builder(each = \"...\")`",
-                                ));
-                            }
-                            ident
-                        }
-                        None => {
-                            return Err(syn::Error::new(
-                                named_value.span(),
-                                "expected path to be an identity \"each\"",
-                            ))
-                        }
-                    };
-                    if let Lit::Str(lit_str) = named_value.lit.clone() {
-                        let value_ident = syn::Ident::new(&lit_str.value(), named_value.span());
-                        Ok(Self {
-                            name_ident: name_ident.clone(),
-                            value_ident,
-                            span: value.span(),
-                        })
-                    } else {
-                        Err(syn::Error::new(
-                            named_value.span(),
-                            "expected `builder(each = \"...\")`",
+/// Options given through `#[builder(setter(...))]`.
+pub(crate) struct SetterAttr {
+    pub(crate) into: bool,
+    pub(crate) strip_option: bool,
+    pub(crate) name: Option<syn::Ident>,
+}
+
+impl Default for SetterAttr {
+    fn default() -> Self {
+        Self {
+            into: false,
+            strip_option: true,
+            name: None,
+        }
+    }
+}
+
+/// How `merge` combines a collection field with the other builder's.
+#[derive(Clone, Copy, Default)]
+pub(crate) enum Merge {
+    /// The other builder's items are added after this one's.
+    #[default]
+    Append,
+    /// The other builder's items replace this one's, unless there are none.
+    Replace,
+}
+
+/// How many fields of a `#[builder(group = "...")]` may be set.
+#[derive(Clone, Copy, PartialEq)]
+pub(crate) enum GroupRule {
+    /// `exactly_one`
+    Exactly,
+    /// `at_least_one`
+    AtLeast,
+    /// `at_most_one`
+    AtMost,
+}
+
+/// What an unset field falls back to in `build()`.
+pub(crate) enum FieldDefault {
+    /// `#[builder(default)]`, i.e. `Default::default()`.
+    Trait,
+    /// `#[builder(default = "...")]`.
+    Expr(Box<syn::Expr>),
+}
+
+impl ToTokens for FieldDefault {
+    fn to_tokens(&self, tokens: &mut TokenStream) {
+        match self {
+            Self::Trait => tokens.extend(quote!(::core::default::Default::default())),
+            Self::Expr(expr) => expr.to_tokens(tokens),
+        }
+    }
+}
+
+impl BuilderAttr {
+    /// Reads the `#[builder(...)]` attributes of a field. Options that are
+    /// wrong are pushed onto `errors` and left out.
+    pub(crate) fn parse(attrs: &[syn::Attribute], errors: &mut Errors) -> Self {
+        let mut builder_attr = Self::default();
+        for item in parse_builder_attrs(attrs, errors) {
+            let applied = builder_attr.apply(&item, errors);
+            errors.record(applied);
+        }
+        builder_attr
+    }
+
+    fn apply(&mut self, item: &AttrItem, errors: &mut Errors) -> syn::Result<()> {
+        match item.key.to_string().as_str() {
+            "each" => self.each = Some(item.expect_ident()?),
+            "default" => self.default = Some(parse_default(item)?),
+            "item" => self.item = Some(parse_item(item)?),
+            "optional" => self.optional = item.expect_bool()?,
+            "repeated" => self.repeated = item.expect_bool()?,
+            "skip" => self.skip = item.expect_bool()?,
+            "sub_builder" => self.sub_builder = item.expect_bool()?,
+            "try_setter" => self.try_setter = item.expect_bool()?,
+            "parse_with" => self.parse_with = Some(item.expect_path()?),
+            "compute" => self.compute = Some(Box::new(item.expect_str()?.parse()?)),
+            "env" => self.env = Some(item.expect_str()?.clone()),
+            "group" => self.group = Some(item.expect_ident()?),
+            "exactly_one" => self.group_rule = Some(parse_group_rule(item, GroupRule::Exactly)?),
+            "at_least_one" => self.group_rule = Some(parse_group_rule(item, GroupRule::AtLeast)?),
+            "at_most_one" => self.group_rule = Some(parse_group_rule(item, GroupRule::AtMost)?),
+            "merge" => {
+                let lit_str = item.expect_str()?;
+                let merge = match lit_str.value().as_str() {
+                    "append" => Merge::Append,
+                    "replace" => Merge::Replace,
+                    _ => {
+                        return Err(syn::Error::new(
+                            lit_str.span(),
+                            "expected `\"append\"` or `\"replace\"`",
                         ))
                     }
-                } else {
-                    Err(syn::Error::new(
-                        meta.span(),
-                        "expected `builder(each = \"...\")`",
-                    ))
+                };
+                self.merge = Some((merge, item.key.span()));
+            }
+            "name" => self.name = Some(item.expect_ident()?),
+            "setter" => {
+                for item in item.expect_list()? {
+                    errors.record(self.apply_setter(&item));
                 }
-            } else {
-                Err(syn::Error::new(
-                    inner.span(),
-                    "expected `builder(each = \"...\")`",
-                ))
             }
-        } else {
-            Err(syn::Error::new(
-                outer_meta.span(),
-                "expected `builder(each = \"...\")`",
-            ))
+            _ => return Err(item.unknown_key(KEYS)),
+        }
+        Ok(())
+    }
+
+    /// Applies an entry of `#[builder(setter(...))]`.
+    fn apply_setter(&mut self, item: &AttrItem) -> syn::Result<()> {
+        match item.key.to_string().as_str() {
+            "into" => self.setter.into = item.expect_bool()?,
+            "strip_option" => self.setter.strip_option = item.expect_bool()?,
+            "name" => self.setter.name = Some(item.expect_ident()?),
+            _ => return Err(item.unknown_key(SETTER_KEYS)),
         }
+        Ok(())
+    }
+}
+
+fn parse_item(item: &AttrItem) -> syn::Result<syn::Type> {
+    match &item.value {
+        AttrValue::Type(ty) => Ok(ty.as_ref().clone()),
+        _ => item.expect_str()?.parse(),
+    }
+}
+
+fn parse_group_rule(item: &AttrItem, rule: GroupRule) -> syn::Result<(GroupRule, Span)> {
+    item.expect_flag()?;
+    Ok((rule, item.key.span()))
+}
+
+fn parse_default(item: &AttrItem) -> syn::Result<FieldDefault> {
+    match &item.value {
+        AttrValue::Flag => Ok(FieldDefault::Trait),
+        AttrValue::Lit(Lit::Str(lit_str)) => Ok(FieldDefault::Expr(Box::new(lit_str.parse()?))),
+        AttrValue::Lit(lit) => Ok(FieldDefault::Expr(Box::new(syn::Expr::Lit(syn::ExprLit {
+            attrs: vec![],
+            lit: lit.clone(),
+        })))),
+        _ => Err(syn::Error::new(
+            item.span(),
+            "expected `builder(default)` or `builder(default = \"...\")`",
+        )),
     }
 }
@@ -0,0 +1,228 @@
+use proc_macro2::TokenStream;
+use quote::{format_ident, quote};
+use syn::Ident;
+
+use crate::{
+    alloc_crate, camel_case, field_info::FieldInfo, struct_attr::StructAttr, BuilderTarget,
+};
+
+/// The `Missing<Field>` variant reported when a required field is not set.
+pub(crate) fn missing_variant(field: &FieldInfo) -> Ident {
+    format_ident!("Missing{}", camel_case(&field.name))
+}
+
+/// The variant wrapping the error of a `#[builder(sub_builder)]` field.
+pub(crate) fn invalid_variant(field: &FieldInfo) -> Ident {
+    format_ident!("Invalid{}", camel_case(&field.name))
+}
+
+/// Generates the error enum returned by `build()`, with one `Missing<Field>`
+/// variant per required field and a `Validation` variant for failed checks.
+/// Builders reading fields from the environment also get an `Env` variant,
+/// and every sub-builder field an `Invalid<Field>` variant wrapping its error.
+/// A `Parse` variant holds why a `parse_<field>` setter rejected its string,
+/// and a `Group` variant the fields set in breach of a group's rule, which are
+/// none of them when at least one was needed.
+///
+/// The error implements `std::error::Error`, or with `#[builder(no_std)]` the
+/// same trait as `core::error::Error`, which needs Rust 1.81.
+pub(crate) fn expand(
+    target: &BuilderTarget,
+    struct_attr: &StructAttr,
+    fields: &[FieldInfo],
+) -> Result<TokenStream, syn::Error> {
+    let alloc = alloc_crate(struct_attr);
+    let BuilderTarget {
+        error_name, vis, ..
+    } = target;
+    let required = fields
+        .iter()
+        .filter(|field| field.is_required())
+        .collect::<Vec<_>>();
+    let variants = required.iter().map(|field| {
+        let variant = missing_variant(field);
+        let cfgs = &field.cfgs;
+        quote!(#(#cfgs)* #variant,)
+    });
+    let field_names = required.iter().map(|field| {
+        let variant = missing_variant(field);
+        let cfgs = &field.cfgs;
+        let name_str = field.name_str();
+        quote! {
+            #(#cfgs)*
+            Self::#variant => ::core::option::Option::Some(#name_str),
+        }
+    });
+    let messages = required.iter().map(|field| {
+        let variant = missing_variant(field);
+        let cfgs = &field.cfgs;
+        let message = format!("{} is not set", field.name_str());
+        quote! {
+            #(#cfgs)*
+            Self::#variant => f.write_str(#message),
+        }
+    });
+    let sub_builders = fields
+        .iter()
+        .filter_map(|field| Some((field, field.sub_builder.as_ref()?)))
+        .collect::<Vec<_>>();
+    let invalid_variants = sub_builders.iter().map(|(field, sub_builder)| {
+        let variant = invalid_variant(field);
+        let cfgs = &field.cfgs;
+        let error = &sub_builder.error;
+        quote!(#(#cfgs)* #variant(#error),)
+    });
+    let invalid_field_names = sub_builders.iter().map(|(field, _)| {
+        let variant = invalid_variant(field);
+        let cfgs = &field.cfgs;
+        let name_str = field.name_str();
+        quote! {
+            #(#cfgs)*
+            Self::#variant(_) => ::core::option::Option::Some(#name_str),
+        }
+    });
+    // Errors about a field of the inner struct read as a path to that field,
+    // e.g. `tls.cert_path is not set`, as every message of an error with a
+    // `field_name()` starts with that name.
+    let invalid_messages = sub_builders.iter().map(|(field, _)| {
+        let variant = invalid_variant(field);
+        let cfgs = &field.cfgs;
+        let name_str = field.name_str();
+        quote! {
+            #(#cfgs)*
+            Self::#variant(err) => match err.field_name() {
+                ::core::option::Option::Some(_) => ::core::write!(f, "{}.{}", #name_str, err),
+                ::core::option::Option::None => ::core::write!(f, "{}: {}", #name_str, err),
+            },
+        }
+    });
+    let parse = fields.iter().any(|field| field.attr.parse_with.is_some());
+    let (parse_variant, parse_field_name, parse_message, parse_failed) = if parse {
+        (
+            quote! {
+                Parse {
+                    field: &'static str,
+                    message: #alloc::string::String,
+                },
+            },
+            quote! {
+                Self::Parse { field, .. } => ::core::option::Option::Some(field),
+            },
+            quote! {
+                Self::Parse { field, message } => {
+                    ::core::write!(f, "{}: invalid value: {}", field, message)
+                }
+            },
+            quote! {
+                impl #error_name {
+                    fn parse_failed(field: &'static str, err: impl ::core::fmt::Display) -> Self {
+                        Self::Parse {
+                            field,
+                            message: #alloc::string::ToString::to_string(&err),
+                        }
+                    }
+                }
+            },
+        )
+    } else {
+        (quote!(), quote!(), quote!(), quote!())
+    };
+    let group = fields.iter().any(|field| field.attr.group.is_some());
+    let (group_variant, group_message) = if group {
+        (
+            quote! {
+                Group {
+                    group: &'static str,
+                    fields: #alloc::vec::Vec<&'static str>,
+                },
+            },
+            quote! {
+                Self::Group { group, fields } => match fields.split_first() {
+                    ::core::option::Option::None => {
+                        ::core::write!(f, "one of the fields in group {} must be set", group)
+                    }
+                    ::core::option::Option::Some((first, rest)) => {
+                        ::core::write!(
+                            f,
+                            "only one of the fields in group {} may be set, got {}",
+                            group,
+                            first,
+                        )?;
+                        for field in rest {
+                            ::core::write!(f, ", {}", field)?;
+                        }
+                        ::core::result::Result::Ok(())
+                    }
+                },
+            },
+        )
+    } else {
+        (quote!(), quote!())
+    };
+    let env = fields.iter().any(|field| field.attr.env.is_some());
+    let (env_variant, env_field_name, env_message) = if env {
+        (
+            quote! {
+                Env {
+                    field: &'static str,
+                    variable: &'static str,
+                    message: #alloc::string::String,
+                },
+            },
+            quote! {
+                Self::Env { field, .. } => ::core::option::Option::Some(field),
+            },
+            quote! {
+                Self::Env {
+                    field,
+                    variable,
+                    message,
+                } => ::core::write!(f, "{}: invalid value in {}: {}", field, variable, message),
+            },
+        )
+    } else {
+        (quote!(), quote!(), quote!())
+    };
+    let error_trait = if struct_attr.no_std {
+        quote!(::core::error::Error)
+    } else {
+        quote!(::std::error::Error)
+    };
+    Ok(quote! {
+        #[derive(::core::fmt::Debug, ::core::clone::Clone, ::core::cmp::PartialEq, ::core::cmp::Eq)]
+        #vis enum #error_name {
+            #(#variants)*
+            Validation(#alloc::string::String),
+            #(#invalid_variants)*
+            #parse_variant
+            #group_variant
+            #env_variant
+        }
+        impl #error_name {
+            /// The name of the field this error is about, if it is about one.
+            #vis fn field_name(&self) -> ::core::option::Option<&'static str> {
+                match self {
+                    #(#field_names)*
+                    #(#invalid_field_names)*
+                    #parse_field_name
+                    #env_field_name
+                    _ => ::core::option::Option::None,
+                }
+            }
+        }
+        impl ::core::fmt::Display for #error_name {
+            fn fmt(&self, f: &mut ::core::fmt::Formatter) -> ::core::fmt::Result {
+                match self {
+                    #(#messages)*
+                    Self::Validation(message) => f.write_str(message),
+                    #(#invalid_messages)*
+                    #parse_message
+                    #group_message
+                    #env_message
+                }
+            }
+        }
+        #parse_failed
+        impl #error_trait for #error_name {}
+    })
+}
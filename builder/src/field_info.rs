@@ -1,31 +1,328 @@
-use syn::{spanned::Spanned, Type};
+use proc_macro2::TokenStream;
+use quote::{format_ident, quote};
+use syn::{ext::IdentExt, spanned::Spanned, Type};
 
-use crate::get_generic_typ;
+use crate::{builder_attr::BuilderAttr, errors::Errors, get_generic_args, get_generic_typ};
 
 pub(crate) struct FieldInfo<'a> {
-    pub(crate) name: &'a syn::Ident,
+    /// The name of the field's member, setter and local in the builder. Tuple
+    /// fields are called `_0`, `_1`, ... unless renamed, and the only field of
+    /// a newtype is called `value`.
+    pub(crate) name: syn::Ident,
+    /// The field in the built struct itself.
+    pub(crate) member: syn::Member,
     pub(crate) ty: &'a syn::Type,
-    pub(crate) optional: Option<&'a Type>,
-    pub(crate) vec: Option<&'a Type>,
+    pub(crate) optional: Option<Type>,
+    pub(crate) collection: Option<Collection>,
+    pub(crate) sub_builder: Option<SubBuilder>,
     pub(crate) span: proc_macro2::Span,
-    pub(crate) attrs: &'a [syn::Attribute],
-}
-
-impl<'a> TryFrom<&'a syn::Field> for FieldInfo<'a> {
-    type Error = syn::Error;
-    fn try_from(field: &'a syn::Field) -> Result<Self, Self::Error> {
-        let optional = get_generic_typ(&field.ty, "Option")?;
-        let vec = get_generic_typ(&field.ty, "Vec")?;
-        Ok(Self {
-            name: field
-                .ident
-                .as_ref()
-                .ok_or_else(|| syn::Error::new(field.span(), "expected field to have a name"))?,
+    pub(crate) attr: BuilderAttr,
+    pub(crate) setter_name: syn::Ident,
+    /// The field's `#[cfg]` attributes, and `#[cfg_attr]` ones adding a
+    /// `cfg`, repeated on everything generated for the field. The compiler
+    /// drops fields whose `cfg` does not hold before deriving, so these only
+    /// ever hold and keep the generated code in step with the field.
+    pub(crate) cfgs: Vec<syn::Attribute>,
+}
+
+impl<'a> FieldInfo<'a> {
+    /// Reads the field's `#[builder]` attributes. Mistakes in them are pushed
+    /// onto `errors` and the offending options are left out, so that a builder
+    /// can still be generated for the rest of the code to use.
+    pub(crate) fn new(
+        index: usize,
+        field: &'a syn::Field,
+        newtype: bool,
+        errors: &mut Errors,
+    ) -> Self {
+        let mut attr = BuilderAttr::parse(&field.attrs, errors);
+        let optional = if attr.optional {
+            Some(into_iter_item(&field.ty))
+        } else {
+            errors
+                .record(get_generic_typ(&field.ty, "Option"))
+                .flatten()
+                .cloned()
+        };
+        let collection = errors.record(get_collection(field, &attr)).flatten();
+        if collection.is_some() && attr.default.is_some() {
+            errors.push(syn::Error::new(
+                field.span(),
+                "`builder(default)` is not supported on collection fields, they already default to empty",
+            ));
+        }
+        if let (Some((_, span)), None) = (&attr.merge, &collection) {
+            errors.push(syn::Error::new(
+                *span,
+                "`builder(merge = \"...\")` is only supported on collection fields",
+            ));
+        }
+        if [attr.skip, attr.compute.is_some(), attr.default.is_some()]
+            .iter()
+            .filter(|given| **given)
+            .count()
+            > 1
+        {
+            errors.push(syn::Error::new(
+                field.span(),
+                "only one of `builder(skip)`, `builder(compute = \"...\")` and `builder(default)` may be given",
+            ));
+        }
+        let sub_builder = if attr.sub_builder {
+            if optional.is_some()
+                || collection.is_some()
+                || attr.default.is_some()
+                || attr.env.is_some()
+            {
+                errors.push(syn::Error::new(
+                    field.span(),
+                    "`builder(sub_builder)` is not supported on optional or collection fields, nor together with `default` or `env`",
+                ));
+            }
+            // The field is set through the builder returned by its accessor,
+            // so there is no setter for these to apply to.
+            if attr.try_setter
+                || attr.parse_with.is_some()
+                || attr.setter.into
+                || attr.each.is_some()
+            {
+                errors.push(syn::Error::new(
+                    field.span(),
+                    "`builder(try_setter)`, `builder(parse_with = ...)`, `builder(setter(into))` and `builder(each = \"...\")` are not supported on sub-builder fields",
+                ));
+                attr.try_setter = false;
+                attr.parse_with = None;
+                attr.setter.into = false;
+            }
+            errors.record(get_sub_builder(&field.ty))
+        } else {
+            None
+        };
+        if let (Some(env), Some(_)) = (&attr.env, &collection) {
+            errors.push(syn::Error::new(
+                env.span(),
+                "`builder(env = \"...\")` is not supported on collection fields",
+            ));
+        }
+        if let Some(group) = &attr.group {
+            if collection.is_some() || sub_builder.is_some() || attr.skip || attr.compute.is_some()
+            {
+                errors.push(syn::Error::new(
+                    group.span(),
+                    "`builder(group = \"...\")` is not supported on collection, sub-builder, skipped or computed fields",
+                ));
+            }
+        }
+        let (name, member) = match &field.ident {
+            Some(ident) => {
+                if let Some(name) = &attr.name {
+                    errors.push(syn::Error::new(
+                        name.span(),
+                        "`builder(name = \"...\")` is only supported on tuple struct fields",
+                    ));
+                }
+                (ident.clone(), syn::Member::Named(ident.clone()))
+            }
+            None => {
+                let name = match &attr.name {
+                    Some(name) => name.clone(),
+                    None if newtype => format_ident!("value"),
+                    None => format_ident!("_{}", index),
+                };
+                (name, syn::Member::Unnamed(index.into()))
+            }
+        };
+        let setter_name = attr.setter.name.clone().unwrap_or_else(|| name.clone());
+        Self {
+            name,
+            member,
             ty: &field.ty,
             optional,
-            vec,
+            collection,
+            sub_builder,
             span: field.span(),
-            attrs: &field.attrs,
+            attr,
+            setter_name,
+            cfgs: get_cfgs(&field.attrs),
+        }
+    }
+}
+
+impl FieldInfo<'_> {
+    /// The name of the field in errors, without the `r#` of a raw identifier.
+    pub(crate) fn name_str(&self) -> String {
+        self.name.unraw().to_string()
+    }
+
+    /// Whether `build()` needs this field to have been set by the caller.
+    pub(crate) fn is_required(&self) -> bool {
+        self.optional.is_none()
+            && self.collection.is_none()
+            && self.sub_builder.is_none()
+            && self.attr.default.is_none()
+    }
+
+    /// Whether the field is filled in by `build()` itself rather than by the
+    /// caller, so it has no setter nor member in the builder.
+    pub(crate) fn is_computed(&self) -> bool {
+        self.attr.skip || self.attr.compute.is_some()
+    }
+
+    /// The name of the one-at-a-time setter of a collection field, if any.
+    pub(crate) fn each(&self) -> Option<&syn::Ident> {
+        self.collection.as_ref().and(self.attr.each.as_ref())
+    }
+
+    /// The type accepted by the setter for the whole field.
+    pub(crate) fn setter_ty(&self) -> &Type {
+        match &self.optional {
+            Some(inner) if self.attr.setter.strip_option => inner,
+            _ => self.ty,
+        }
+    }
+
+    /// Whether the setter for the whole field stores its value as `Some(val)`.
+    pub(crate) fn setter_wraps_some(&self) -> bool {
+        self.collection.is_none() && (self.optional.is_none() || self.attr.setter.strip_option)
+    }
+
+    /// The `arg` parameter of a setter accepting `ty`, along with `arg`
+    /// converted to `ty`.
+    pub(crate) fn setter_arg(&self, arg: &str, ty: &Type) -> (TokenStream, TokenStream) {
+        let arg = format_ident!("{}", arg);
+        if self.attr.setter.into {
+            (
+                quote!(#arg: impl ::core::convert::Into<#ty>),
+                quote!(::core::convert::Into::into(#arg)),
+            )
+        } else {
+            (quote!(#arg: #ty), quote!(#arg))
+        }
+    }
+}
+
+/// The `#[cfg]` attributes among `attrs`, along with the `cfg`s that
+/// `#[cfg_attr]` attributes conditionally add. Other attributes a `cfg_attr`
+/// adds are dropped, as they are meant for the field rather than the builder.
+fn get_cfgs(attrs: &[syn::Attribute]) -> Vec<syn::Attribute> {
+    attrs
+        .iter()
+        .filter_map(|attr| {
+            if attr.path.is_ident("cfg") {
+                return Some(attr.clone());
+            }
+            if !attr.path.is_ident("cfg_attr") {
+                return None;
+            }
+            let list = match attr.parse_meta() {
+                Ok(syn::Meta::List(list)) => list,
+                _ => return None,
+            };
+            let mut nested = list.nested.iter();
+            let predicate = nested.next()?;
+            let cfgs = nested
+                .filter(|meta| {
+                    matches!(meta, syn::NestedMeta::Meta(syn::Meta::List(list)) if list.path.is_ident("cfg"))
+                })
+                .collect::<Vec<_>>();
+            if cfgs.is_empty() {
+                return None;
+            }
+            Some(syn::parse_quote!(#[cfg_attr(#predicate, #(#cfgs),*)]))
         })
+        .collect()
+}
+
+/// The items a collection field can be extended with one at a time.
+pub(crate) enum Collection {
+    /// `Vec<T>`, `HashSet<T>`, ... or whatever was given as `item = T`.
+    Item(Box<Type>),
+    /// `HashMap<K, V>` and `BTreeMap<K, V>`, extended by a key and a value.
+    Map(Box<Type>, Box<Type>),
+}
+
+fn get_collection(field: &syn::Field, attr: &BuilderAttr) -> syn::Result<Option<Collection>> {
+    if let Some(item) = &attr.item {
+        if attr.each.is_none() {
+            return Err(syn::Error::new(
+                item.span(),
+                "`builder(item = ...)` requires `builder(each = \"...\")`",
+            ));
+        }
+        return Ok(Some(Collection::Item(Box::new(item.clone()))));
+    }
+    if attr.repeated {
+        return Ok(Some(Collection::Item(Box::new(into_iter_item(&field.ty)))));
+    }
+    // Vec fields have always defaulted to empty, with or without `each`.
+    if let Some(item) = get_generic_typ(&field.ty, "Vec")? {
+        return Ok(Some(Collection::Item(Box::new(item.clone()))));
+    }
+    let each = match &attr.each {
+        Some(each) => each,
+        None => return Ok(None),
+    };
+    for name in [
+        "VecDeque",
+        "LinkedList",
+        "BinaryHeap",
+        "HashSet",
+        "BTreeSet",
+    ] {
+        if let Some([item, ..]) = get_generic_args(&field.ty, name).as_deref() {
+            return Ok(Some(Collection::Item(Box::new((*item).clone()))));
+        }
     }
+    for name in ["HashMap", "BTreeMap"] {
+        if let Some([key, value, ..]) = get_generic_args(&field.ty, name).as_deref() {
+            return Ok(Some(Collection::Map(
+                Box::new((*key).clone()),
+                Box::new((*value).clone()),
+            )));
+        }
+    }
+    Err(syn::Error::new(
+        each.span(),
+        "expected a Vec, set or map field, or `builder(item = ...)` naming the type to extend it with",
+    ))
+}
+
+/// The builder of a `#[builder(sub_builder)]` field and the error it returns.
+///
+/// The inner builder cannot be a typestate one, and is cloned along with the
+/// rest of an immutable outer builder, so it has to be `Clone` for those.
+pub(crate) struct SubBuilder {
+    pub(crate) builder: Type,
+    pub(crate) error: Type,
+}
+
+/// The `TlsConfigBuilder` and `TlsConfigBuilderError` generated next to a
+/// `TlsConfig` field type, keeping its module path and generic arguments.
+fn get_sub_builder(ty: &Type) -> syn::Result<SubBuilder> {
+    let path = match ty {
+        Type::Path(type_path) if type_path.qself.is_none() => &type_path.path,
+        _ => {
+            return Err(syn::Error::new(
+                ty.span(),
+                "`builder(sub_builder)` expects the field type to be a struct deriving Builder",
+            ))
+        }
+    };
+    let mut builder = path.clone();
+    let mut error = path.clone();
+    if let (Some(builder), Some(error)) = (builder.segments.last_mut(), error.segments.last_mut()) {
+        builder.ident = format_ident!("{}Builder", builder.ident);
+        error.ident = format_ident!("{}BuilderError", error.ident);
+        error.arguments = syn::PathArguments::None;
+    }
+    Ok(SubBuilder {
+        builder: syn::parse_quote!(#builder),
+        error: syn::parse_quote!(#error),
+    })
+}
+
+/// The item type of `ty` when iterated over, which for an `Option<T>` or
+/// `Vec<T>` hidden behind a type alias is `T`.
+fn into_iter_item(ty: &Type) -> Type {
+    syn::parse_quote!(<#ty as ::core::iter::IntoIterator>::Item)
 }
@@ -0,0 +1,123 @@
+use proc_macro2::TokenStream;
+use quote::quote;
+use syn::{ext::IdentExt, Ident};
+
+use crate::{builder_attr::GroupRule, cfg_block, errors::Errors, field_info::FieldInfo};
+
+/// Fields sharing a `#[builder(group = "...")]`, of which only so many may be
+/// set.
+pub(crate) struct Group<'a> {
+    pub(crate) name: &'a Ident,
+    pub(crate) rule: GroupRule,
+    pub(crate) members: Vec<&'a FieldInfo<'a>>,
+}
+
+impl Group<'_> {
+    /// Whether `field` is one of the members of the group.
+    pub(crate) fn contains(&self, field: &FieldInfo) -> bool {
+        self.members.iter().any(|member| member.name == field.name)
+    }
+}
+
+/// Gathers the groups of `fields` in the order they are first mentioned.
+///
+/// The rule may be given on any member of a group, but only one rule per
+/// group. Members that `build()` could not do without are left out, since
+/// leaving them unset is what the group is there to allow.
+pub(crate) fn collect<'a>(fields: &'a [FieldInfo<'a>], errors: &mut Errors) -> Vec<Group<'a>> {
+    let mut groups = Vec::<(&Ident, Option<GroupRule>, Vec<&FieldInfo>)>::new();
+    for field in fields {
+        let name = match &field.attr.group {
+            Some(name) => name,
+            None => {
+                if let Some((_, span)) = field.attr.group_rule {
+                    errors.push(syn::Error::new(
+                        span,
+                        "expected `builder(group = \"...\")` next to the group's rule",
+                    ));
+                }
+                continue;
+            }
+        };
+        let index = match groups.iter().position(|(group, ..)| *group == name) {
+            Some(index) => index,
+            None => {
+                groups.push((name, None, vec![]));
+                groups.len() - 1
+            }
+        };
+        let (_, rule, members) = &mut groups[index];
+        if let Some((field_rule, span)) = field.attr.group_rule {
+            match rule {
+                Some(rule) if *rule != field_rule => errors.push(syn::Error::new(
+                    span,
+                    format!("group `{}` already has a different rule", name),
+                )),
+                _ => *rule = Some(field_rule),
+            }
+        }
+        if field.is_required() {
+            errors.push(syn::Error::new(
+                field.span,
+                "fields in a group must be `Option`s or have a `builder(default)`",
+            ));
+        } else if field.collection.is_none() && field.sub_builder.is_none() {
+            members.push(field);
+        }
+    }
+    groups
+        .into_iter()
+        .filter_map(|(name, rule, members)| match rule {
+            Some(rule) => Some(Group {
+                name,
+                rule,
+                members,
+            }),
+            None => {
+                errors.push(syn::Error::new(
+                    name.span(),
+                    format!(
+                        "group `{}` needs one of `exactly_one`, `at_least_one` or `at_most_one` on one of its fields",
+                        name
+                    ),
+                ));
+                None
+            }
+        })
+        .collect()
+}
+
+/// The check in `build()` that the group's rule holds, returning a `Group`
+/// error with the members that were set otherwise.
+pub(crate) fn expand_check(group: &Group, error_name: &Ident, alloc: &TokenStream) -> TokenStream {
+    let group_str = group.name.unraw().to_string();
+    let pushes = group.members.iter().map(|member| {
+        let name = &member.name;
+        let name_str = member.name_str();
+        cfg_block(
+            member,
+            quote! {
+                if ::core::option::Option::is_some(&self.#name) {
+                    set.push(#name_str);
+                }
+            },
+        )
+    });
+    let failed = match group.rule {
+        GroupRule::Exactly => quote!(set.len() != 1),
+        GroupRule::AtLeast => quote!(set.is_empty()),
+        GroupRule::AtMost => quote!(set.len() > 1),
+    };
+    quote! {
+        {
+            let mut set = #alloc::vec::Vec::new();
+            #(#pushes)*
+            if #failed {
+                return ::core::result::Result::Err(#error_name::Group {
+                    group: #group_str,
+                    fields: set,
+                });
+            }
+        }
+    }
+}
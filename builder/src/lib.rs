@@ -1,126 +1,999 @@
-use builder_attr::BuilderAttr;
-use field_info::FieldInfo;
-use proc_macro2::TokenStream;
-use quote::{quote, quote_spanned};
-use syn::{spanned::Spanned, Data, DeriveInput, Fields, Ident, PathArguments, Type};
+use builder_attr::{FieldDefault, GroupRule, Merge};
+use errors::Errors;
+use field_info::{Collection, FieldInfo};
+use group::Group;
+use proc_macro2::{Span, TokenStream};
+use quote::{format_ident, quote, quote_spanned};
+use struct_attr::{Pattern, StructAttr};
+use syn::{
+    ext::IdentExt, spanned::Spanned, Data, DeriveInput, Fields, Generics, Ident, PathArguments,
+    Type,
+};
 
+mod attr_parser;
 mod builder_attr;
+mod builder_error;
+mod errors;
 mod field_info;
+mod group;
+mod struct_attr;
+mod typestate;
 
 #[proc_macro_derive(Builder, attributes(builder))]
 pub fn derive(input: proc_macro::TokenStream) -> proc_macro::TokenStream {
     let parsed = syn::parse_macro_input!(input as syn::DeriveInput);
-    proc_macro::TokenStream::from(match impl_builder_derive(&parsed.ident, &parsed) {
+    proc_macro::TokenStream::from(match impl_builder_derive(&parsed) {
         Ok(res) => res,
         Err(e) => e.to_compile_error(),
     })
 }
 
-fn impl_builder_derive(struct_name: &Ident, ast: &DeriveInput) -> Result<TokenStream, syn::Error> {
-    Ok(match &ast.data {
-        Data::Struct(data) => match &data.fields {
-            Fields::Named(fields) => {
-                let builder_name = syn::Ident::new(&format!("{struct_name}Builder"), ast.span());
-                let fields = fields
-                    .named
-                    .iter()
-                    .map(TryInto::try_into)
-                    .collect::<Result<Vec<_>, _>>()?;
-                let setters = get_setters(&fields)?;
-                let members = get_members(&fields)?;
-                let checks = get_checks(struct_name, &fields)?;
-                quote! {
-                    #[derive(Default)]
-                    struct #builder_name {
-                        #members
-                    }
-                    impl #builder_name {
-                        #setters
-                        fn build(&mut self) -> ::std::result::Result<#struct_name, ::std::boxed::Box<dyn ::std::error::Error>> {
-                            Ok(#checks)
-                        }
-                    }
-                    impl #struct_name {
-                        fn builder() -> #builder_name {
-                            Default::default()
-                        }
-                    }
-                }
+/// What a single generated builder builds.
+pub(crate) struct BuilderTarget<'a> {
+    /// The type returned by `build()`, which also gets the constructor.
+    pub(crate) ty: &'a Ident,
+    /// The struct or enum variant `build()` constructs.
+    pub(crate) path: TokenStream,
+    pub(crate) generics: &'a Generics,
+    pub(crate) builder_name: Ident,
+    pub(crate) error_name: Ident,
+    /// The associated function on `ty` returning a new builder.
+    pub(crate) constructor: Ident,
+    /// The method of the builder returning what it built.
+    pub(crate) build_fn: Ident,
+    /// Whether `path` is an enum variant, so that not every value of `ty` can
+    /// be turned back into this builder.
+    pub(crate) is_variant: bool,
+    /// The visibility of the builder, its error and all of their methods.
+    pub(crate) vis: syn::Visibility,
+}
+
+fn impl_builder_derive(ast: &DeriveInput) -> Result<TokenStream, syn::Error> {
+    let ty = &ast.ident;
+    match &ast.data {
+        Data::Struct(data) => {
+            let builder_name = format_ident!("{}Builder", ty);
+            let target = BuilderTarget {
+                ty,
+                path: quote!(#ty),
+                generics: &ast.generics,
+                error_name: format_ident!("{}Error", builder_name),
+                builder_name,
+                constructor: format_ident!("builder"),
+                build_fn: format_ident!("build"),
+                is_variant: false,
+                vis: ast.vis.clone(),
+            };
+            expand_builder(target, &ast.attrs, &data.fields)
+        }
+        // Every variant gets a builder of its own, e.g. `Request::Get { .. }`
+        // is built by a `RequestGetBuilder` returned from `Request::get_builder()`.
+        Data::Enum(data) => {
+            // The enum's own attributes apply to every variant, so mistakes in
+            // them are reported here once rather than once per variant.
+            let mut errors = Errors::default();
+            let enum_attr = StructAttr::parse(&ast.attrs, &mut errors);
+            if let Some(span) = enum_attr.to_builder {
+                errors.push(to_builder_on_enum(span));
             }
-            _ => Err(syn::Error::new(ast.span(), "expected named fields"))?,
-        },
-        _ => Err(syn::Error::new(
+            for ident in [&enum_attr.name, &enum_attr.constructor]
+                .into_iter()
+                .flatten()
+            {
+                errors.push(syn::Error::new(
+                    ident.span(),
+                    "`builder(name = \"...\")` and `builder(constructor = \"...\")` go on the variants of an enum, each of which gets a builder of its own",
+                ));
+            }
+            let enum_attrs = if errors.is_empty() {
+                ast.attrs.as_slice()
+            } else {
+                &[]
+            };
+            let builders = data
+                .variants
+                .iter()
+                .map(|variant| {
+                    let variant_name = &variant.ident;
+                    let builder_name = format_ident!("{}{}Builder", ty, variant_name);
+                    let target = BuilderTarget {
+                        ty,
+                        path: quote!(#ty::#variant_name),
+                        generics: &ast.generics,
+                        error_name: format_ident!("{}Error", builder_name),
+                        builder_name,
+                        constructor: format_ident!("{}_builder", snake_case(variant_name)),
+                        build_fn: format_ident!("build"),
+                        is_variant: true,
+                        vis: ast.vis.clone(),
+                    };
+                    let attrs = enum_attrs
+                        .iter()
+                        .chain(&variant.attrs)
+                        .cloned()
+                        .collect::<Vec<_>>();
+                    expand_builder(target, &attrs, &variant.fields)
+                })
+                .collect::<Result<TokenStream, syn::Error>>()?;
+            let errors = errors.finish().err().map(|error| error.to_compile_error());
+            Ok(quote! {
+                #errors
+                #builders
+            })
+        }
+        Data::Union(_) => Err(syn::Error::new(
             ast.span(),
-            "Builder derive is only supported on structs",
-        ))?,
+            "Builder derive is only supported on structs and enums",
+        )),
+    }
+}
+
+fn expand_builder(
+    mut target: BuilderTarget,
+    attrs: &[syn::Attribute],
+    fields: &Fields,
+) -> Result<TokenStream, syn::Error> {
+    let mut errors = Errors::default();
+    let struct_attr = StructAttr::parse(attrs, &mut errors);
+    if let Some(vis) = &struct_attr.vis {
+        target.vis = vis.clone();
+    }
+    if let Some(name) = &struct_attr.name {
+        target.error_name = format_ident!("{}Error", name);
+        target.builder_name = name.clone();
+    }
+    if let Some(constructor) = &struct_attr.constructor {
+        target.constructor = constructor.clone();
+    }
+    if let Some(build_fn) = &struct_attr.build_fn {
+        target.build_fn = build_fn.clone();
+    }
+    let target = &target;
+    let newtype = matches!(fields, Fields::Unnamed(unnamed) if unnamed.unnamed.len() == 1);
+    // Skipped and computed fields never make it into the builder itself, only
+    // into `build()`.
+    let (mut fields, computed): (Vec<_>, Vec<_>) = fields
+        .iter()
+        .enumerate()
+        .map(|(index, field)| FieldInfo::new(index, field, newtype, &mut errors))
+        .partition(|field| !field.is_computed());
+    if let Some(prefix) = &struct_attr.setter_prefix {
+        for field in fields
+            .iter_mut()
+            .filter(|field| field.attr.setter.name.is_none())
+        {
+            field.setter_name = format_ident!("{}{}", prefix, field.name);
+        }
+    }
+    if struct_attr.default {
+        for field in fields.iter_mut().filter(|field| {
+            field.optional.is_none() && field.collection.is_none() && field.sub_builder.is_none()
+        }) {
+            field.attr.default.get_or_insert(FieldDefault::Trait);
+        }
+    }
+    if let (Some(span), true) = (struct_attr.to_builder, target.is_variant) {
+        errors.push(to_builder_on_enum(span));
+    }
+    if struct_attr.no_std {
+        for env in fields.iter_mut().filter_map(|field| field.attr.env.take()) {
+            errors.push(syn::Error::new(
+                env.span(),
+                "`builder(env = \"...\")` needs `std` and is not supported with `builder(no_std)`",
+            ));
+        }
+    }
+    if struct_attr.typestate {
+        for env in fields.iter().filter_map(|field| field.attr.env.as_ref()) {
+            errors.push(syn::Error::new(
+                env.span(),
+                "`builder(env = \"...\")` is not supported on typestate builders",
+            ));
+        }
+        for field in fields
+            .iter_mut()
+            .filter(|field| field.sub_builder.is_some())
+        {
+            errors.push(syn::Error::new(
+                field.span,
+                "`builder(sub_builder)` is not supported on typestate builders",
+            ));
+            field.sub_builder = None;
+        }
+        for field in fields
+            .iter_mut()
+            .filter(|field| field.attr.try_setter || field.attr.parse_with.is_some())
+        {
+            errors.push(syn::Error::new(
+                field.span,
+                "`builder(try_setter)` and `builder(parse_with = ...)` are not supported on typestate builders",
+            ));
+            field.attr.try_setter = false;
+            field.attr.parse_with = None;
+        }
+    }
+    // Collections named by their item type need only be `Default + Extend`,
+    // which leaves no way to tell what the other builder's holds.
+    if struct_attr.merge.is_some() {
+        for item in fields
+            .iter()
+            .filter(|field| field.collection.is_some())
+            .filter_map(|field| field.attr.item.as_ref())
+        {
+            errors.push(syn::Error::new(
+                item.span(),
+                "`builder(item = ...)` collections cannot be merged, so they are not supported with `builder(merge)`",
+            ));
+        }
+    }
+    if struct_attr.merge.is_none() {
+        for (_, span) in fields.iter().filter_map(|field| field.attr.merge) {
+            errors.push(syn::Error::new(
+                span,
+                "`builder(merge = \"...\")` requires `#[builder(merge)]` on the struct",
+            ));
+        }
+    }
+    let groups = group::collect(&fields, &mut errors);
+    let builder = if struct_attr.typestate {
+        typestate::expand(target, &struct_attr, &fields, &computed, &groups)?
+    } else {
+        expand_checked(target, &struct_attr, &fields, &computed, &groups)?
+    };
+    // The builder is emitted even next to attribute errors, leaving out only
+    // the options that were wrong, so that code using it does not pile up
+    // errors about missing types and methods on top of the real ones.
+    let errors = errors.finish().err().map(|error| error.to_compile_error());
+    Ok(quote! {
+        #errors
+        #builder
     })
 }
 
-fn get_setters(fields: &[FieldInfo]) -> Result<TokenStream, syn::Error> {
-    let field_setters = fields
+fn to_builder_on_enum(span: Span) -> syn::Error {
+    syn::Error::new(span, "`builder(to_builder)` is not supported on enums")
+}
+
+/// Expands a builder whose `build()` checks for missing fields at runtime.
+fn expand_checked(
+    target: &BuilderTarget,
+    struct_attr: &StructAttr,
+    fields: &[FieldInfo],
+    computed: &[FieldInfo],
+    groups: &[Group],
+) -> Result<TokenStream, syn::Error> {
+    let BuilderTarget {
+        ty,
+        generics,
+        builder_name,
+        error_name,
+        constructor,
+        build_fn,
+        vis,
+        ..
+    } = target;
+    let setters = get_setters(target, fields, struct_attr.pattern)?;
+    let members = get_members(fields)?;
+    let defaults = get_defaults(fields)?;
+    let checks = get_checks(target, struct_attr, fields, computed, groups)?;
+    let error = builder_error::expand(target, struct_attr, fields)?;
+    let where_clause = &generics.where_clause;
+    let (impl_generics, ty_generics, _) = generics.split_for_impl();
+    let prefill = get_prefill(
+        target,
+        struct_attr,
+        fields,
+        quote!(#builder_name #ty_generics),
+        false,
+    );
+    let merge = get_merge(target, struct_attr, fields);
+    let env = get_env(target, fields);
+    let introspection = get_introspection(target, struct_attr, fields, groups);
+    let receiver = match struct_attr.pattern {
+        Pattern::Owned => quote!(self),
+        Pattern::Mutable => quote!(&mut self),
+        Pattern::Immutable => quote!(&self),
+    };
+    let builder_attrs = get_builder_attrs(struct_attr, struct_attr.pattern == Pattern::Immutable);
+    // What a `sub_builder` field of another builder calls, which does not know
+    // this builder's pattern nor what its `build()` is called. An owned builder
+    // is left empty, as its `build()` consumes it.
+    let nested = match struct_attr.pattern {
+        Pattern::Owned => quote!(::core::mem::take(self).#build_fn()),
+        Pattern::Mutable | Pattern::Immutable => quote!(self.#build_fn()),
+    };
+    let build_nested = quote! {
+        #[doc(hidden)]
+        #vis fn __build_nested(&mut self) -> ::core::result::Result<#ty #ty_generics, #error_name> {
+            #nested
+        }
+    };
+    let parse_errors = fields.iter().filter_map(|field| {
+        let member = parse_error_member(field)?;
+        let cfgs = &field.cfgs;
+        Some(quote!(#(#cfgs)* #member: ::core::option::Option<#error_name>,))
+    });
+    let no_parse_errors = no_parse_errors(fields);
+    // The phantom member keeps generic parameters that an enum variant does
+    // not use itself from being rejected as unused by the builder.
+    Ok(quote! {
+        #error
+        #builder_attrs
+        #vis struct #builder_name #generics #where_clause {
+            #members
+            #(#parse_errors)*
+            __phantom: ::core::marker::PhantomData<fn() -> #ty #ty_generics>,
+        }
+        impl #impl_generics ::core::default::Default for #builder_name #ty_generics #where_clause {
+            fn default() -> Self {
+                Self {
+                    #defaults
+                    #no_parse_errors
+                    __phantom: ::core::marker::PhantomData,
+                }
+            }
+        }
+        // Using a `#[deprecated]` field is for the caller to be warned about,
+        // not the builder.
+        #[allow(deprecated)]
+        impl #impl_generics #builder_name #ty_generics #where_clause {
+            #setters
+            #merge
+            #env
+            #introspection
+            #vis fn #build_fn(#receiver) -> ::core::result::Result<#ty #ty_generics, #error_name> {
+                #checks
+            }
+            #build_nested
+        }
+        impl #impl_generics #ty #ty_generics #where_clause {
+            #vis fn #constructor() -> #builder_name #ty_generics {
+                ::core::default::Default::default()
+            }
+        }
+        #prefill
+    })
+}
+
+/// The derives and pass-through attributes of the builder struct, with
+/// `Clone` derived as well if `clone` is set and it is not there already.
+fn get_builder_attrs(struct_attr: &StructAttr, clone: bool) -> TokenStream {
+    let mut derives = struct_attr
+        .derives
+        .iter()
+        .map(|path| quote!(#path))
+        .collect::<Vec<_>>();
+    let derives_clone = struct_attr.derives.iter().any(|path| {
+        path.segments
+            .last()
+            .is_some_and(|last| last.ident == "Clone")
+    });
+    if clone && !derives_clone {
+        derives.push(quote!(::core::clone::Clone));
+    }
+    let derive = if derives.is_empty() {
+        quote!()
+    } else {
+        quote!(#[derive(#(#derives),*)])
+    };
+    let struct_attrs = &struct_attr.struct_attrs;
+    quote! {
+        #derive
+        #(#[#struct_attrs])*
+    }
+}
+
+/// Generates `From<Ty>` for the builder `builder_ty`, and `Ty::to_builder()`
+/// if asked for, both returning a builder with every field already set.
+///
+/// A field that is `None` in the value is left unset, so an `Option` field
+/// with a `default` gets that default back when the builder is built.
+fn get_prefill(
+    target: &BuilderTarget,
+    struct_attr: &StructAttr,
+    fields: &[FieldInfo],
+    builder_ty: TokenStream,
+    typestate: bool,
+) -> TokenStream {
+    if target.is_variant {
+        return quote!();
+    }
+    let BuilderTarget {
+        ty,
+        path,
+        generics,
+        builder_name,
+        vis,
+        ..
+    } = target;
+    let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
+    let bindings = fields
         .iter()
         .map(|field| {
-            let arg_typ = field.optional.unwrap_or(&field.ty);
-            let field_name = field.name;
-            if let Some(vec_typ) = field.vec {
-                let attr = field.attrs.iter().next();
-                match attr {
-                    Some(attr) => {
-                        let builder_attr: BuilderAttr = attr.try_into()?;
-                        let func_name = &builder_attr.value_ident;
-                        let mut setter = quote_spanned! { field.span.clone() =>
-                            fn #func_name(&mut self, val: #vec_typ) -> &mut Self {
-                                self.#field_name.push(val);
-                                self
+            let name = &field.name;
+            let cfgs = &field.cfgs;
+            match &field.member {
+                syn::Member::Named(ident) if ident == name => quote!(#(#cfgs)* #name),
+                member => quote!(#(#cfgs)* #member: #name),
+            }
+        })
+        .collect::<Vec<_>>();
+    let prefill = |value: &dyn Fn(&Ident) -> TokenStream| {
+        fields
+            .iter()
+            .map(|field| {
+                let name = &field.name;
+                let cfgs = &field.cfgs;
+                let value = value(name);
+                let value = if typestate && field.is_required() {
+                    quote!((#value,))
+                } else if field.sub_builder.is_some() {
+                    quote!(::core::option::Option::Some(::core::convert::From::from(#value)))
+                } else if field.optional.is_some() || field.collection.is_some() {
+                    value
+                } else {
+                    quote!(::core::option::Option::Some(#value))
+                };
+                quote!(#(#cfgs)* #name: #value,)
+            })
+            .collect::<Vec<_>>()
+    };
+    let moved = prefill(&|name| quote!(#name));
+    let no_parse_errors = no_parse_errors(fields);
+    let to_builder = struct_attr.to_builder.map(|_| {
+        let cloned = prefill(&|name| quote!(::core::clone::Clone::clone(#name)));
+        quote! {
+            #[allow(deprecated)]
+            impl #impl_generics #ty #ty_generics #where_clause {
+                #vis fn to_builder(&self) -> #builder_ty {
+                    let #path { #(#bindings,)* .. } = self;
+                    #builder_name {
+                        #(#cloned)*
+                        #no_parse_errors
+                        __phantom: ::core::marker::PhantomData,
+                    }
+                }
+            }
+        }
+    });
+    quote! {
+        #[allow(deprecated)]
+        impl #impl_generics ::core::convert::From<#ty #ty_generics> for #builder_ty #where_clause {
+            fn from(value: #ty #ty_generics) -> Self {
+                let #path { #(#bindings,)* .. } = value;
+                Self {
+                    #(#moved)*
+                    #no_parse_errors
+                    __phantom: ::core::marker::PhantomData,
+                }
+            }
+        }
+        #to_builder
+    }
+}
+
+/// Generates `merge`, and `merge_from` unless the builder is owned and so
+/// does not require its fields to be `Clone`, for `#[builder(merge)]`.
+///
+/// Fields set in the other builder win, collections are appended to or
+/// replaced by the other builder's as given by `#[builder(merge = "...")]`.
+/// Sub-builders are merged field by field through their own `merge` and
+/// `merge_from`, so the inner struct needs `#[builder(merge)]` as well.
+/// Collections given by `#[builder(item = ...)]` are rejected beforehand.
+fn get_merge(
+    target: &BuilderTarget,
+    struct_attr: &StructAttr,
+    fields: &[FieldInfo],
+) -> TokenStream {
+    if struct_attr.merge.is_none() {
+        return quote!();
+    }
+    let vis = &target.vis;
+    let merged = |cloned: bool| {
+        fields
+            .iter()
+            .map(|field| {
+                let name = &field.name;
+                let value = if cloned {
+                    quote!(::core::clone::Clone::clone(&other.#name))
+                } else {
+                    quote!(other.#name)
+                };
+                if field.sub_builder.is_some() {
+                    let (theirs, merge) = if cloned {
+                        (quote!(&other.#name), quote!(merge_from))
+                    } else {
+                        (quote!(other.#name), quote!(merge))
+                    };
+                    return cfg_block(
+                        field,
+                        quote! {
+                            if let ::core::option::Option::Some(theirs) = #theirs {
+                                ::core::option::Option::get_or_insert_with(
+                                    &mut self.#name,
+                                    ::core::default::Default::default,
+                                )
+                                .#merge(theirs);
                             }
-                        };
-                        if func_name.to_string() != field_name.to_string() {
-                            setter = quote_spanned! { field.span.clone() =>
-                                #setter
-
-                                fn #field_name(&mut self, val: #arg_typ) -> &mut Self {
-                                    self.#field_name = val;
-                                    self
-                                }
+                        },
+                    );
+                }
+                if field.collection.is_some() && field.attr.item.is_some() {
+                    return quote!();
+                }
+                if field.collection.is_none() {
+                    return cfg_block(
+                        field,
+                        quote! {
+                            if ::core::option::Option::is_some(&other.#name) {
+                                self.#name = #value;
                             }
+                        },
+                    );
+                }
+                let merged = match field.attr.merge.map(|(merge, _)| merge).unwrap_or_default() {
+                    Merge::Append => quote! {
+                        ::core::iter::Extend::extend(&mut self.#name, #value);
+                    },
+                    Merge::Replace => quote! {
+                        if ::core::iter::IntoIterator::into_iter(&other.#name)
+                            .next()
+                            .is_some()
+                        {
+                            self.#name = #value;
                         }
-                        Ok(setter)
-                    }
-                    None => Ok(quote_spanned! { field.span.clone() =>
-                        fn #field_name(&mut self, val: #arg_typ) -> &mut Self {
-                            self.#field_name = val;
-                            self
+                    },
+                };
+                cfg_block(field, merged)
+            })
+            .collect::<Vec<_>>()
+    };
+    // A parse error goes along with the field: the other builder's replaces
+    // this one's, and setting the field in the other builder clears it. These
+    // come first, before the other builder's fields are moved out.
+    let merged_parse_errors = |cloned: bool| {
+        fields
+            .iter()
+            .filter_map(|field| {
+                let member = parse_error_member(field)?;
+                let name = &field.name;
+                let theirs = if cloned {
+                    quote!(::core::clone::Clone::clone(&other.#member))
+                } else {
+                    quote!(other.#member)
+                };
+                let field_set = field
+                    .collection
+                    .is_none()
+                    .then(|| quote!(|| ::core::option::Option::is_some(&other.#name)));
+                Some(cfg_block(
+                    field,
+                    quote! {
+                        if ::core::option::Option::is_some(&other.#member) #field_set {
+                            self.#member = #theirs;
                         }
-                    }),
+                    },
+                ))
+            })
+            .collect::<Vec<_>>()
+    };
+    let mut moved = merged_parse_errors(false);
+    moved.extend(merged(false));
+    let merge_from = match struct_attr.pattern {
+        Pattern::Owned => quote!(),
+        Pattern::Mutable | Pattern::Immutable => {
+            let mut cloned = merged_parse_errors(true);
+            cloned.extend(merged(true));
+            quote! {
+                #vis fn merge_from(&mut self, other: &Self) -> &mut Self {
+                    #(#cloned)*
+                    self
                 }
-            } else {
-                Ok(quote_spanned! { field.span.clone() =>
-                    fn #field_name(&mut self, val: #arg_typ) -> &mut Self {
-                        self.#field_name = Some(val);
-                        self
-                    }
-                })
             }
+        }
+    };
+    quote! {
+        #vis fn merge(&mut self, other: Self) -> &mut Self {
+            #(#moved)*
+            self
+        }
+        #merge_from
+    }
+}
+
+/// Generates `from_env` and `fill_from_env` if any field has an
+/// `#[builder(env = "...")]` variable to be parsed from with `FromStr`.
+fn get_env(target: &BuilderTarget, fields: &[FieldInfo]) -> TokenStream {
+    let BuilderTarget {
+        error_name, vis, ..
+    } = target;
+    let filled = fields
+        .iter()
+        .filter_map(|field| {
+            let variable = field.attr.env.as_ref()?;
+            let name = &field.name;
+            let name_str = field.name_str();
+            let ty = field.optional.as_ref().unwrap_or(field.ty);
+            let clear_parse_error = parse_error_member(field)
+                .map(|member| quote!(self.#member = ::core::option::Option::None;));
+            let error = quote! {
+                #error_name::Env {
+                    field: #name_str,
+                    variable: #variable,
+                    message: ::std::string::ToString::to_string(&err),
+                }
+            };
+            Some(cfg_block(
+                field,
+                quote! {
+                    if ::core::option::Option::is_none(&self.#name) {
+                        match ::std::env::var(#variable) {
+                            ::core::result::Result::Ok(val) => {
+                                let val = <#ty as ::core::str::FromStr>::from_str(&val)
+                                    .map_err(|err| #error)?;
+                                self.#name = ::core::option::Option::Some(val);
+                                #clear_parse_error
+                            }
+                            ::core::result::Result::Err(::std::env::VarError::NotPresent) => {}
+                            ::core::result::Result::Err(err) => {
+                                return ::core::result::Result::Err(#error);
+                            }
+                        }
+                    }
+                },
+            ))
         })
+        .collect::<Vec<_>>();
+    if filled.is_empty() {
+        return quote!();
+    }
+    quote! {
+        /// A builder with every field that has an environment variable set
+        /// read from it.
+        #vis fn from_env() -> ::core::result::Result<Self, #error_name> {
+            let mut builder: Self = ::core::default::Default::default();
+            builder.fill_from_env()?;
+            ::core::result::Result::Ok(builder)
+        }
+        /// Reads every field that is not set yet and has an environment
+        /// variable set from that variable.
+        #vis fn fill_from_env(&mut self) -> ::core::result::Result<&mut Self, #error_name> {
+            #(#filled)*
+            ::core::result::Result::Ok(self)
+        }
+    }
+}
+
+fn get_setters(
+    target: &BuilderTarget,
+    fields: &[FieldInfo],
+    pattern: Pattern,
+) -> Result<TokenStream, syn::Error> {
+    let field_setters = fields
+        .iter()
+        .map(|field| Ok(field_setters(target, field, pattern)))
         .collect::<Result<Vec<_>, syn::Error>>()?;
     Ok(quote! {
         #(#field_setters)*
     })
 }
 
+/// How a method modifying the builder in the style of `pattern` takes `self`
+/// and what it returns, along with the builder to modify and return, which
+/// for an immutable builder is a clone made by the last of these.
+fn setter_receiver(pattern: Pattern) -> (TokenStream, TokenStream, TokenStream, TokenStream) {
+    match pattern {
+        Pattern::Owned => (quote!(mut self), quote!(Self), quote!(self), quote!()),
+        Pattern::Mutable => (quote!(&mut self), quote!(&mut Self), quote!(self), quote!()),
+        Pattern::Immutable => (
+            quote!(&self),
+            quote!(Self),
+            quote!(builder),
+            quote!(let mut builder = ::core::clone::Clone::clone(self);),
+        ),
+    }
+}
+
+/// Generates `missing_fields()`, `reset()` and per field `clear_<field>()`,
+/// plus `is_<field>_set()` for fields that are not collections, as these are
+/// never missing and need not be iterable, for `#[builder(introspect)]`.
+///
+/// A sub-builder field is missing when its builder is missing any field, so
+/// the inner struct needs `#[builder(introspect)]` as well. A group that needs
+/// one of its fields set and has none is missing under its own name. Strings
+/// that failed to parse and groups with too many fields set are left to
+/// `build()` to report.
+fn get_introspection(
+    target: &BuilderTarget,
+    struct_attr: &StructAttr,
+    fields: &[FieldInfo],
+    groups: &[Group],
+) -> TokenStream {
+    if struct_attr.introspect.is_none() {
+        return quote!();
+    }
+    let vis = &target.vis;
+    let alloc = alloc_crate(struct_attr);
+    let (receiver, ret, builder, clone) = setter_receiver(struct_attr.pattern);
+    let missing = fields.iter().filter_map(|field| {
+        let name = &field.name;
+        let name_str = field.name_str();
+        let is_missing = if let Some(sub_builder) = &field.sub_builder {
+            let builder_ty = &sub_builder.builder;
+            quote! {
+                !match &self.#name {
+                    ::core::option::Option::Some(builder) => builder.missing_fields(),
+                    ::core::option::Option::None => {
+                        <#builder_ty as ::core::default::Default>::default().missing_fields()
+                    }
+                }
+                .is_empty()
+            }
+        } else if field.is_required() {
+            quote!(::core::option::Option::is_none(&self.#name))
+        } else {
+            return None;
+        };
+        Some(cfg_block(
+            field,
+            quote! {
+                if #is_missing {
+                    missing.push(#name_str);
+                }
+            },
+        ))
+    });
+    let missing_groups = groups
+        .iter()
+        .filter(|group| group.rule != GroupRule::AtMost)
+        .map(|group| {
+            let group_str = group.name.unraw().to_string();
+            let set_checks = group.members.iter().map(|member| {
+                let name = &member.name;
+                cfg_block(
+                    member,
+                    quote! {
+                        if ::core::option::Option::is_some(&self.#name) {
+                            set = true;
+                        }
+                    },
+                )
+            });
+            quote! {
+                {
+                    let mut set = false;
+                    #(#set_checks)*
+                    if !set {
+                        missing.push(#group_str);
+                    }
+                }
+            }
+        });
+    let per_field = fields.iter().map(|field| {
+        let name = &field.name;
+        let cfgs = &field.cfgs;
+        let clear = format_ident!("clear_{}", name);
+        let clear_parse_error = parse_error_member(field)
+            .map(|member| quote!(#builder.#member = ::core::option::Option::None;));
+        let is_set = field.collection.is_none().then(|| {
+            let is_set = format_ident!("is_{}_set", name);
+            quote! {
+                #(#cfgs)*
+                #vis fn #is_set(&self) -> bool {
+                    ::core::option::Option::is_some(&self.#name)
+                }
+            }
+        });
+        quote_spanned! { field.span =>
+            #is_set
+            #(#cfgs)*
+            #vis fn #clear(#receiver) -> #ret {
+                #clone
+                #builder.#name = ::core::default::Default::default();
+                #clear_parse_error
+                #builder
+            }
+        }
+    });
+    let reset = match struct_attr.pattern {
+        Pattern::Owned => quote! {
+            #vis fn reset(self) -> Self {
+                ::core::default::Default::default()
+            }
+        },
+        Pattern::Immutable => quote! {
+            #vis fn reset(&self) -> Self {
+                ::core::default::Default::default()
+            }
+        },
+        Pattern::Mutable => quote! {
+            #vis fn reset(&mut self) -> &mut Self {
+                *self = ::core::default::Default::default();
+                self
+            }
+        },
+    };
+    quote! {
+        /// The names of the fields `build()` would report as not set, followed
+        /// by those of the groups it would report as having none set.
+        #vis fn missing_fields(&self) -> #alloc::vec::Vec<&'static str> {
+            #[allow(unused_mut)]
+            let mut missing = #alloc::vec::Vec::new();
+            #(#missing)*
+            #(#missing_groups)*
+            missing
+        }
+        #(#per_field)*
+        #reset
+    }
+}
+
+/// The setters of a single field in the style of the given builder pattern.
+fn field_setters(target: &BuilderTarget, field: &FieldInfo, pattern: Pattern) -> TokenStream {
+    let BuilderTarget {
+        error_name, vis, ..
+    } = target;
+    let (receiver, ret, builder, clone) = setter_receiver(pattern);
+    let name = &field.name;
+    let setter_name = &field.setter_name;
+    let cfgs = &field.cfgs;
+    if let Some(sub_builder) = &field.sub_builder {
+        let builder_ty = &sub_builder.builder;
+        return quote_spanned! { field.span =>
+            #(#cfgs)*
+            #vis fn #setter_name(&mut self) -> &mut #builder_ty {
+                ::core::option::Option::get_or_insert_with(
+                    &mut self.#name,
+                    ::core::default::Default::default,
+                )
+            }
+        };
+    }
+    let mut setters = quote!();
+    if let (Some(collection), Some(each)) = (&field.collection, field.each()) {
+        let (params, item, item_ty) = match collection {
+            Collection::Item(item_ty) => {
+                let (param, value) = field.setter_arg("val", item_ty);
+                (param, value, quote!(#item_ty))
+            }
+            Collection::Map(key_ty, value_ty) => {
+                let (key_param, key) = field.setter_arg("key", key_ty);
+                let (value_param, value) = field.setter_arg("value", value_ty);
+                (
+                    quote!(#key_param, #value_param),
+                    quote!((#key, #value)),
+                    quote!((#key_ty, #value_ty)),
+                )
+            }
+        };
+        let extend = format_ident!("extend_{}", name);
+        setters.extend(quote_spanned! { field.span =>
+            #(#cfgs)*
+            #vis fn #each(#receiver, #params) -> #ret {
+                #clone
+                ::core::iter::Extend::extend(&mut #builder.#name, ::core::iter::once(#item));
+                #builder
+            }
+            #(#cfgs)*
+            #vis fn #extend(#receiver, iter: impl ::core::iter::IntoIterator<Item = #item_ty>) -> #ret {
+                #clone
+                ::core::iter::Extend::extend(&mut #builder.#name, iter);
+                #builder
+            }
+        });
+    }
+    let ty = field.setter_ty();
+    // Setting the whole field, whichever way, clears the error of a string
+    // that failed to parse into it.
+    let parse_error = parse_error_member(field);
+    let parse_ok = parse_error
+        .as_ref()
+        .map(|member| quote!(#builder.#member = ::core::option::Option::None;));
+    let stored = |value: TokenStream| {
+        if field.setter_wraps_some() {
+            quote!(::core::option::Option::Some(#value))
+        } else {
+            value
+        }
+    };
+    // A one-at-a-time setter with the same name replaces the whole-field one.
+    if field.each() != Some(setter_name) {
+        let (param, value) = field.setter_arg("val", ty);
+        let stored = stored(value);
+        setters.extend(quote_spanned! { field.span =>
+            #(#cfgs)*
+            #vis fn #setter_name(#receiver, #param) -> #ret {
+                #clone
+                #builder.#name = #stored;
+                #parse_ok
+                #builder
+            }
+        });
+    }
+    if field.attr.try_setter {
+        let try_name = format_ident!("try_{}", setter_name);
+        let stored = stored(quote!(::core::convert::TryInto::try_into(val)?));
+        setters.extend(quote_spanned! { field.span =>
+            #(#cfgs)*
+            #vis fn #try_name<__Input: ::core::convert::TryInto<#ty>>(
+                #receiver,
+                val: __Input,
+            ) -> ::core::result::Result<#ret, __Input::Error> {
+                #clone
+                #builder.#name = #stored;
+                #parse_ok
+                ::core::result::Result::Ok(#builder)
+            }
+        });
+    }
+    // A string that fails to parse leaves the field as it was and is reported
+    // by `build()`, so that callers can set everything before checking.
+    if let (Some(parse_with), Some(member)) = (&field.attr.parse_with, &parse_error) {
+        let parse_name = format_ident!("parse_{}", setter_name);
+        let name_str = field.name_str();
+        let stored = stored(quote!(val));
+        setters.extend(quote_spanned! { field.span =>
+            #(#cfgs)*
+            #vis fn #parse_name(#receiver, val: &str) -> #ret {
+                #clone
+                match #parse_with(val) {
+                    ::core::result::Result::Ok(val) => {
+                        #builder.#name = #stored;
+                        #parse_ok
+                    }
+                    ::core::result::Result::Err(err) => {
+                        #builder.#member = ::core::option::Option::Some(
+                            #error_name::parse_failed(#name_str, err),
+                        );
+                    }
+                }
+                #builder
+            }
+        });
+    }
+    setters
+}
+
+/// The member keeping the error of the last string that the field's
+/// `parse_<field>` setter rejected until `build()`, if it has that setter.
+fn parse_error_member(field: &FieldInfo) -> Option<Ident> {
+    field
+        .attr
+        .parse_with
+        .as_ref()
+        .map(|_| format_ident!("__{}_parse_error", field.name))
+}
+
+/// The parse error members of a new builder, with no errors in them.
+fn no_parse_errors(fields: &[FieldInfo]) -> TokenStream {
+    let members = fields.iter().filter_map(|field| {
+        let member = parse_error_member(field)?;
+        let cfgs = &field.cfgs;
+        Some(quote!(#(#cfgs)* #member: ::core::option::Option::None,))
+    });
+    quote!(#(#members)*)
+}
+
 fn get_members(fields: &[FieldInfo]) -> Result<TokenStream, syn::Error> {
     let members = fields
         .iter()
         .map(|field| {
-            let name = field.name;
-            let option_type = field.optional.unwrap_or(field.ty);
-            if let Some(inner_type) = field.vec {
+            let name = &field.name;
+            let cfgs = &field.cfgs;
+            let option_type = match &field.sub_builder {
+                Some(sub_builder) => &sub_builder.builder,
+                None => field.optional.as_ref().unwrap_or(field.ty),
+            };
+            if field.collection.is_some() {
+                let ty = field.ty;
                 Ok(quote! {
-                    #name: ::std::vec::Vec<#inner_type>,
+                    #(#cfgs)*
+                    #name: #ty,
                 })
             } else {
                 Ok(quote! {
-                    #name: ::std::option::Option<#option_type>,
+                    #(#cfgs)*
+                    #name: ::core::option::Option<#option_type>,
                 })
             }
         })
@@ -130,55 +1003,290 @@ fn get_members(fields: &[FieldInfo]) -> Result<TokenStream, syn::Error> {
     })
 }
 
-fn get_checks(struct_name: &Ident, fields: &[FieldInfo]) -> Result<TokenStream, syn::Error> {
+fn get_defaults(fields: &[FieldInfo]) -> Result<TokenStream, syn::Error> {
+    let defaults = fields.iter().map(|field| {
+        let name = &field.name;
+        let cfgs = &field.cfgs;
+        quote! {
+            #(#cfgs)*
+            #name: ::core::default::Default::default(),
+        }
+    });
+    Ok(quote! {
+        #(#defaults)*
+    })
+}
+
+fn get_checks(
+    target: &BuilderTarget,
+    struct_attr: &StructAttr,
+    fields: &[FieldInfo],
+    computed: &[FieldInfo],
+    groups: &[Group],
+) -> Result<TokenStream, syn::Error> {
+    let error_name = &target.error_name;
+    let path = &target.path;
+    // Fields are resolved into locals in declaration order so that a
+    // `default = "..."` expression can refer to the fields declared before it,
+    // and computed fields come last so they can refer to all of the others.
     let checks = fields
         .iter()
-        .map(|field| {
-            let name = field.name;
-            Ok(if field.optional.is_some() || field.vec.is_some() {
-                quote! {
-                    #name: self.#name.clone(),
-                }
-            } else {
-                quote! {
-                    #name: self.#name.clone().ok_or_else(|| String::from("#name is not set"))?,
-                }
-            })
-        })
+        .map(|field| Ok(resolve_field(field, error_name, struct_attr.pattern)))
         .collect::<Result<Vec<_>, syn::Error>>()?;
+    let parse_errors = fields.iter().filter_map(|field| {
+        let member = parse_error_member(field)?;
+        Some(cfg_block(
+            field,
+            quote! {
+                if let ::core::option::Option::Some(err) = &self.#member {
+                    return ::core::result::Result::Err(::core::clone::Clone::clone(err));
+                }
+            },
+        ))
+    });
+    let alloc = alloc_crate(struct_attr);
+    let group_checks = groups
+        .iter()
+        .map(|group| group::expand_check(group, error_name, &alloc));
+    let computed_checks = computed.iter().map(resolve_computed);
+    let built_members = fields.iter().chain(computed).map(built_member);
+    let validation = get_validation(error_name, struct_attr);
     Ok(quote! {
-        #struct_name {
+        #(#parse_errors)*
+        #(#group_checks)*
+        // The field locals are kept to a block of their own, so that they do
+        // not shadow whatever `validate` names.
+        let built = {
             #(#checks)*
-        }
+            #(#computed_checks)*
+            #path {
+                #(#built_members)*
+            }
+        };
+        #validation
+        ::core::result::Result::Ok(built)
     })
 }
 
-fn get_generic_typ<'a>(typ: &'a Type, gen_name: &str) -> Result<Option<&'a Type>, syn::Error> {
-    Ok(match typ {
-        Type::Path(type_path) => {
-            if type_path.path.segments.len() != 1 {
-                None
-            } else {
-                let segment = &type_path.path.segments[0];
-                if segment.ident.to_string() != gen_name {
-                    None
-                } else {
-                    match &segment.arguments {
-                        PathArguments::AngleBracketed(args) => {
-                            if args.args.len() != 1 {
-                                None
-                            } else {
-                                match &args.args[0] {
-                                    syn::GenericArgument::Type(typ) => Some(typ),
-                                    _ => None,
-                                }
-                            }
-                        }
-                        _ => None,
+/// Binds the final value of a field to a local of the same name, moving it
+/// out of an owned builder and cloning it out of a borrowed one.
+fn resolve_field(field: &FieldInfo, error_name: &Ident, pattern: Pattern) -> TokenStream {
+    let name = &field.name;
+    let cfgs = &field.cfgs;
+    let member = match pattern {
+        Pattern::Owned => quote!(self.#name),
+        Pattern::Mutable | Pattern::Immutable => {
+            quote!(::core::clone::Clone::clone(&self.#name))
+        }
+    };
+    if let Some(sub_builder) = &field.sub_builder {
+        let builder_ty = &sub_builder.builder;
+        let variant = builder_error::invalid_variant(field);
+        // The inner builder is built in place through `__build_nested`, which
+        // works whatever the inner builder's pattern and `build_fn` are.
+        let built = match pattern {
+            Pattern::Owned => quote! {
+                ::core::option::Option::unwrap_or_default(self.#name).__build_nested()
+            },
+            Pattern::Mutable => quote! {
+                match &mut self.#name {
+                    ::core::option::Option::Some(builder) => builder.__build_nested(),
+                    ::core::option::Option::None => {
+                        <#builder_ty as ::core::default::Default>::default().__build_nested()
                     }
                 }
+            },
+            Pattern::Immutable => quote! {
+                ::core::option::Option::unwrap_or_default(::core::clone::Clone::clone(&self.#name))
+                    .__build_nested()
+            },
+        };
+        // Typestate builders have no `Default`, which is all that can be told
+        // about the inner builder from here, so say what is wrong with it.
+        let check = quote_spanned! { field.ty.span() =>
+            fn sub_builder_needs_a_builder_that_is_not_typestate<B: ::core::default::Default>() {}
+            sub_builder_needs_a_builder_that_is_not_typestate::<#builder_ty>();
+        };
+        return quote! {
+            #(#cfgs)*
+            let #name = {
+                #check
+                #built.map_err(#error_name::#variant)?
+            };
+        };
+    }
+    match (&field.attr.default, &field.optional) {
+        (Some(default), Some(_)) => quote! {
+            #(#cfgs)*
+            let #name = match #member {
+                ::core::option::Option::Some(val) => ::core::option::Option::Some(val),
+                ::core::option::Option::None => #default,
+            };
+        },
+        (Some(default), None) => quote! {
+            #(#cfgs)*
+            let #name = match #member {
+                ::core::option::Option::Some(val) => val,
+                ::core::option::Option::None => #default,
+            };
+        },
+        (None, _) if field.is_required() => {
+            let variant = builder_error::missing_variant(field);
+            quote! {
+                #(#cfgs)*
+                let #name = #member.ok_or(#error_name::#variant)?;
             }
         }
+        (None, _) => quote! {
+            #(#cfgs)*
+            let #name = #member;
+        },
+    }
+}
+
+/// Binds the value of a skipped or computed field to a local of the same name.
+fn resolve_computed(field: &FieldInfo) -> TokenStream {
+    let name = &field.name;
+    let cfgs = &field.cfgs;
+    let ty = field.ty;
+    let value = match &field.attr.compute {
+        Some(expr) => quote!(#expr),
+        None => quote!(::core::default::Default::default()),
+    };
+    quote! {
+        #(#cfgs)*
+        let #name: #ty = #value;
+    }
+}
+
+/// The field of the built struct initialized from the local of the same name.
+fn built_member(field: &FieldInfo) -> TokenStream {
+    let name = &field.name;
+    let member = &field.member;
+    let cfgs = &field.cfgs;
+    quote!(#(#cfgs)* #member: #name,)
+}
+
+/// `stmts` behind the `cfg`s of `field`, in a block for the attributes to go
+/// on.
+fn cfg_block(field: &FieldInfo, stmts: TokenStream) -> TokenStream {
+    if field.cfgs.is_empty() {
+        return stmts;
+    }
+    let cfgs = &field.cfgs;
+    quote! {
+        #(#cfgs)*
+        {
+            #stmts
+        }
+    }
+}
+
+/// Runs the `#[builder(validate = ...)]` hook, if any, against `built`.
+fn get_validation(error_name: &Ident, struct_attr: &StructAttr) -> TokenStream {
+    let alloc = alloc_crate(struct_attr);
+    match &struct_attr.validate {
+        Some(validate) => quote! {
+            #validate(&built).map_err(|err| {
+                #error_name::Validation(#alloc::string::ToString::to_string(&err))
+            })?;
+        },
+        None => quote!(),
+    }
+}
+
+/// The crate `String` and `ToString` are used from, which is `alloc` rather
+/// than `std` for `#[builder(no_std)]`.
+fn alloc_crate(struct_attr: &StructAttr) -> TokenStream {
+    if struct_attr.no_std {
+        quote!(::alloc)
+    } else {
+        quote!(::std)
+    }
+}
+
+fn get_generic_typ<'a>(typ: &'a Type, gen_name: &str) -> Result<Option<&'a Type>, syn::Error> {
+    Ok(match get_generic_args(typ, gen_name).as_deref() {
+        Some([typ]) => Some(typ),
         _ => None,
     })
 }
+
+/// The type arguments of `typ` if it is written as `gen_name<...>`, either on
+/// its own or as a path into `std`, `core` or `alloc` such as
+/// `::std::collections::HashMap<K, V>`.
+fn get_generic_args<'a>(typ: &'a Type, gen_name: &str) -> Option<Vec<&'a Type>> {
+    let path = match typ {
+        Type::Path(type_path) if type_path.qself.is_none() => &type_path.path,
+        _ => return None,
+    };
+    let segments = path.segments.iter().collect::<Vec<_>>();
+    let (segment, prefix) = segments.split_last()?;
+    if segment.ident != gen_name {
+        return None;
+    }
+    if let Some(root) = prefix.first() {
+        if !["std", "core", "alloc"]
+            .iter()
+            .any(|krate| root.ident == krate)
+            || prefix.iter().any(|module| !module.arguments.is_empty())
+        {
+            return None;
+        }
+    } else if path.leading_colon.is_some() {
+        return None;
+    }
+    match &segment.arguments {
+        PathArguments::AngleBracketed(args) => args
+            .args
+            .iter()
+            .map(|arg| match arg {
+                syn::GenericArgument::Type(typ) => Some(typ),
+                _ => None,
+            })
+            .collect(),
+        _ => None,
+    }
+}
+
+/// `HTTPPost` as `http_post`: a run of capitals is one word, except for the
+/// last capital if it starts a lowercase word of its own.
+fn snake_case(ident: &Ident) -> String {
+    let name = ident.to_string();
+    let chars = name.trim_start_matches("r#").chars().collect::<Vec<_>>();
+    let mut snake = String::new();
+    for (i, &ch) in chars.iter().enumerate() {
+        if ch.is_uppercase() {
+            let prev = i.checked_sub(1).map(|prev| chars[prev]);
+            let next = chars.get(i + 1);
+            let starts_word = match prev {
+                None | Some('_') => false,
+                Some(prev) if prev.is_uppercase() => next.is_some_and(|next| next.is_lowercase()),
+                Some(_) => true,
+            };
+            if starts_word {
+                snake.push('_');
+            }
+            snake.extend(ch.to_lowercase());
+        } else {
+            snake.push(ch);
+        }
+    }
+    snake
+}
+
+fn camel_case(ident: &Ident) -> String {
+    ident
+        .to_string()
+        .trim_start_matches("r#")
+        .split('_')
+        .filter(|word| !word.is_empty())
+        .map(|word| {
+            let mut chars = word.chars();
+            chars
+                .next()
+                .map(|first| first.to_uppercase().chain(chars).collect::<String>())
+                .unwrap_or_default()
+        })
+        .collect()
+}
@@ -0,0 +1,186 @@
+use proc_macro2::Span;
+use syn::spanned::Spanned;
+
+use crate::{
+    attr_parser::{parse_builder_attrs, AttrItem},
+    errors::Errors,
+};
+
+const KEYS: &[&str] = &[
+    "typestate",
+    "default",
+    "validate",
+    "pattern",
+    "setter",
+    "to_builder",
+    "merge",
+    "introspect",
+    "vis",
+    "derive",
+    "struct_attr",
+    "no_std",
+    "name",
+    "build_fn",
+    "constructor",
+];
+const SETTER_KEYS: &[&str] = &["prefix"];
+
+/// Options given through `#[builder(...)]` on the struct itself.
+#[derive(Default)]
+pub(crate) struct StructAttr {
+    pub(crate) typestate: bool,
+    pub(crate) default: bool,
+    pub(crate) validate: Option<syn::Path>,
+    pub(crate) setter_prefix: Option<String>,
+    pub(crate) pattern: Pattern,
+    /// Where `to_builder` was given, if it was.
+    pub(crate) to_builder: Option<Span>,
+    /// Where `merge` was given, if it was.
+    pub(crate) merge: Option<Span>,
+    /// Where `introspect` was given, if it was.
+    pub(crate) introspect: Option<Span>,
+    /// The visibility of the builder, if not the same as the struct's.
+    pub(crate) vis: Option<syn::Visibility>,
+    /// Traits derived for the builder on top of what it always implements.
+    pub(crate) derives: Vec<syn::Path>,
+    /// Attributes passed through to the builder struct.
+    pub(crate) struct_attrs: Vec<syn::Meta>,
+    /// Whether the generated code may only use `core` and `alloc`.
+    pub(crate) no_std: bool,
+    /// The name of the builder in place of `<Struct>Builder`.
+    pub(crate) name: Option<syn::Ident>,
+    /// The name of the builder's `build()`.
+    pub(crate) build_fn: Option<syn::Ident>,
+    /// The name of the struct's `builder()`.
+    pub(crate) constructor: Option<syn::Ident>,
+}
+
+/// How the builder's setters and `build()` take `self`.
+#[derive(Default, Clone, Copy, PartialEq)]
+pub(crate) enum Pattern {
+    /// Setters and `build()` take and return the builder by value.
+    Owned,
+    /// Setters take and return `&mut Self`, `build()` clones out of `&mut self`.
+    #[default]
+    Mutable,
+    /// Setters take `&self` and return a modified copy, `build()` clones.
+    Immutable,
+}
+
+impl StructAttr {
+    /// Reads the `#[builder(...)]` attributes of a struct. Options that are
+    /// wrong are pushed onto `errors` and left out.
+    pub(crate) fn parse(attrs: &[syn::Attribute], errors: &mut Errors) -> Self {
+        let mut struct_attr = Self::default();
+        let mut pattern_span = None;
+        for item in parse_builder_attrs(attrs, errors) {
+            let applied = struct_attr.apply(&item, &mut pattern_span, errors);
+            errors.record(applied);
+        }
+        if let Some(span) = pattern_span {
+            if struct_attr.typestate && struct_attr.pattern != Pattern::Owned {
+                errors.push(syn::Error::new(
+                    span,
+                    "typestate builders always use the `\"owned\"` pattern",
+                ));
+                struct_attr.pattern = Pattern::Owned;
+            }
+        }
+        if let (Some(span), true) = (struct_attr.merge, struct_attr.typestate) {
+            errors.push(syn::Error::new(
+                span,
+                "`builder(merge)` is not supported on typestate builders",
+            ));
+            struct_attr.merge = None;
+        }
+        if let (Some(span), true) = (struct_attr.introspect, struct_attr.typestate) {
+            errors.push(syn::Error::new(
+                span,
+                "`builder(introspect)` is not supported on typestate builders",
+            ));
+            struct_attr.introspect = None;
+        }
+        struct_attr
+    }
+
+    fn apply(
+        &mut self,
+        item: &AttrItem,
+        pattern_span: &mut Option<Span>,
+        errors: &mut Errors,
+    ) -> syn::Result<()> {
+        match item.key.to_string().as_str() {
+            "typestate" => {
+                item.expect_flag()?;
+                self.typestate = true;
+            }
+            "default" => {
+                item.expect_flag()?;
+                self.default = true;
+            }
+            "to_builder" => {
+                item.expect_flag()?;
+                self.to_builder = Some(item.key.span());
+            }
+            "merge" => {
+                item.expect_flag()?;
+                self.merge = Some(item.key.span());
+            }
+            "introspect" => {
+                item.expect_flag()?;
+                self.introspect = Some(item.key.span());
+            }
+            "no_std" => {
+                item.expect_flag()?;
+                self.no_std = true;
+            }
+            "vis" => self.vis = Some(item.expect_str()?.parse()?),
+            "name" => self.name = Some(item.expect_ident()?),
+            "build_fn" => self.build_fn = Some(item.expect_ident()?),
+            "constructor" => self.constructor = Some(item.expect_ident()?),
+            "derive" => {
+                for path in item.expect_list_of::<syn::Path>()? {
+                    if path.is_ident("Default") {
+                        errors.push(syn::Error::new(
+                            path.span(),
+                            "builders always implement `Default`, it cannot be derived",
+                        ));
+                    } else {
+                        self.derives.push(path);
+                    }
+                }
+            }
+            "struct_attr" => self.struct_attrs.extend(item.expect_list_of()?),
+            "validate" => self.validate = Some(item.expect_path()?),
+            "pattern" => {
+                let lit_str = item.expect_str()?;
+                *pattern_span = Some(lit_str.span());
+                self.pattern = match lit_str.value().as_str() {
+                    "owned" => Pattern::Owned,
+                    "mutable" => Pattern::Mutable,
+                    "immutable" => Pattern::Immutable,
+                    _ => {
+                        return Err(syn::Error::new(
+                            lit_str.span(),
+                            "expected `\"owned\"`, `\"mutable\"` or `\"immutable\"`",
+                        ))
+                    }
+                }
+            }
+            "setter" => {
+                for item in item.expect_list()? {
+                    match item.key.to_string().as_str() {
+                        "prefix" => {
+                            if let Some(prefix) = errors.record(item.expect_str()) {
+                                self.setter_prefix = Some(prefix.value());
+                            }
+                        }
+                        _ => errors.push(item.unknown_key(SETTER_KEYS)),
+                    }
+                }
+            }
+            _ => return Err(item.unknown_key(KEYS)),
+        }
+        Ok(())
+    }
+}
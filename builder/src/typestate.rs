@@ -0,0 +1,361 @@
+use proc_macro2::TokenStream;
+use quote::{format_ident, quote, quote_spanned};
+use syn::{GenericParam, Generics, Ident};
+
+use crate::{
+    builder_attr::GroupRule,
+    builder_error, built_member, camel_case,
+    field_info::FieldInfo,
+    field_setters, get_builder_attrs, get_prefill, get_validation,
+    group::Group,
+    resolve_computed, resolve_field,
+    struct_attr::{Pattern, StructAttr},
+    BuilderTarget,
+};
+
+/// Expands a `#[builder(typestate)]` builder.
+///
+/// Every required field gets its own type parameter on the builder which is
+/// `()` until the setter is called and `(T,)` afterwards. `build()` is only
+/// implemented once all of those parameters are set, so forgetting a required
+/// field is a compile error rather than a runtime one. `build()` returns the
+/// struct directly unless there is a `validate` hook that could reject it.
+///
+/// Every field group gets a type parameter as well, which is `()` until one of
+/// its fields is set and `((),)` afterwards. Unless the group allows several
+/// fields to be set, its setters are only implemented while it is `()`, and
+/// unless it allows none, `build()` only once it is `((),)`.
+pub(crate) fn expand(
+    target: &BuilderTarget,
+    struct_attr: &StructAttr,
+    fields: &[FieldInfo],
+    computed: &[FieldInfo],
+    groups: &[Group],
+) -> Result<TokenStream, syn::Error> {
+    let BuilderTarget {
+        ty,
+        path,
+        generics,
+        builder_name,
+        error_name,
+        constructor,
+        build_fn,
+        vis,
+        ..
+    } = target;
+    // Fields and groups are told apart by their prefix, as a field called
+    // `group_auth` would otherwise clash with a group called `auth`.
+    let states = fields
+        .iter()
+        .filter(|field| field.is_required())
+        .map(|field| format_ident!("__State{}", camel_case(&field.name)))
+        .collect::<Vec<_>>();
+    let group_states = groups
+        .iter()
+        .map(|group| format_ident!("__Group{}", camel_case(group.name)))
+        .collect::<Vec<_>>();
+    let all_states = states.iter().chain(&group_states).collect::<Vec<_>>();
+    let unset = all_states.iter().map(|_| quote!(())).collect::<Vec<_>>();
+    let set = fields
+        .iter()
+        .filter(|field| field.is_required())
+        .map(|field| {
+            let ty = field.ty;
+            quote!((#ty,))
+        })
+        .collect::<Vec<_>>();
+    // A value turned back into a builder counts as having its groups set.
+    let prefilled = set
+        .iter()
+        .cloned()
+        .chain(groups.iter().map(|_| quote!(((),))))
+        .collect::<Vec<_>>();
+    let buildable = set
+        .iter()
+        .cloned()
+        .chain(
+            groups
+                .iter()
+                .zip(&group_states)
+                .map(|(group, state)| match group.rule {
+                    GroupRule::AtMost => quote!(#state),
+                    GroupRule::Exactly | GroupRule::AtLeast => quote!(((),)),
+                }),
+        )
+        .collect::<Vec<_>>();
+
+    // The state parameters are appended after the struct's own parameters, so
+    // defaults on those have to be dropped: defaulted parameters may only trail.
+    let mut struct_generics = Generics::clone(generics);
+    for param in struct_generics.params.iter_mut() {
+        match param {
+            GenericParam::Type(ty) => {
+                ty.eq_token = None;
+                ty.default = None;
+            }
+            GenericParam::Const(konst) => {
+                konst.eq_token = None;
+                konst.default = None;
+            }
+            GenericParam::Lifetime(_) => {}
+        }
+    }
+    let mut builder_generics = struct_generics.clone();
+    builder_generics.params.extend(
+        all_states
+            .iter()
+            .map(|state| -> GenericParam { syn::parse_quote!(#state) }),
+    );
+    // The builder's type on its own names it with nothing set, which is what
+    // a `sub_builder` field of another builder refers to it by.
+    let mut declared_generics = struct_generics.clone();
+    declared_generics.params.extend(
+        all_states
+            .iter()
+            .map(|state| -> GenericParam { syn::parse_quote!(#state = ()) }),
+    );
+    let mut build_generics = struct_generics.clone();
+    build_generics.params.extend(
+        groups
+            .iter()
+            .zip(&group_states)
+            .filter(|(group, _)| group.rule == GroupRule::AtMost)
+            .map(|(_, state)| -> GenericParam { syn::parse_quote!(#state) }),
+    );
+    let struct_args = generic_args(generics);
+    let where_clause = &generics.where_clause;
+    let (impl_generics, ty_generics, _) = struct_generics.split_for_impl();
+    let (builder_impl_generics, _, _) = builder_generics.split_for_impl();
+    let (build_impl_generics, _, _) = build_generics.split_for_impl();
+
+    let mut state_index = 0;
+    let mut members = vec![];
+    let mut initial_members = vec![];
+    let mut setters = vec![];
+    let mut group_setters = vec![];
+    let mut resolved = vec![];
+    for field in fields {
+        let name = &field.name;
+        let ty = field.ty;
+        if field.is_required() {
+            let state = &states[state_index];
+            let next_states = all_states.iter().enumerate().map(|(i, state)| {
+                if i == state_index {
+                    quote!((#ty,))
+                } else {
+                    quote!(#state)
+                }
+            });
+            let other_members = other_members(fields, field);
+            members.push(quote!(#name: #state,));
+            initial_members.push(quote!(#name: (),));
+            let setter_name = &field.setter_name;
+            let (param, value) = field.setter_arg("val", ty);
+            setters.push(quote_spanned! { field.span =>
+                #vis fn #setter_name(self, #param) -> #builder_name<#(#struct_args,)* #(#next_states),*> {
+                    #builder_name {
+                        #name: (#value,),
+                        #(#other_members)*
+                        __phantom: ::core::marker::PhantomData,
+                    }
+                }
+            });
+            resolved.push(quote!(let #name = self.#name.0;));
+            state_index += 1;
+        } else {
+            let cfgs = &field.cfgs;
+            initial_members.push(quote!(#(#cfgs)* #name: ::core::default::Default::default(),));
+            resolved.push(resolve_field(field, error_name, Pattern::Owned));
+            if field.optional.is_none() && field.collection.is_none() {
+                members.push(quote!(#(#cfgs)* #name: ::core::option::Option<#ty>,));
+            } else {
+                members.push(quote!(#(#cfgs)* #name: #ty,));
+            }
+            match groups.iter().position(|group| group.contains(field)) {
+                Some(index) => group_setters.push(group_setter(
+                    target,
+                    fields,
+                    field,
+                    &groups[index],
+                    group_states[index].clone(),
+                    &builder_generics,
+                    &all_states,
+                )),
+                None => setters.push(field_setters(target, field, Pattern::Owned)),
+            }
+        }
+    }
+
+    resolved.extend(computed.iter().map(resolve_computed));
+    let built_members = fields.iter().chain(computed).map(built_member);
+    // The field locals are kept to a block of their own, so that they do not
+    // shadow whatever `validate` names.
+    let built = quote! {
+        let built = {
+            #(#resolved)*
+            #path {
+                #(#built_members)*
+            }
+        };
+    };
+    let prefill = get_prefill(
+        target,
+        struct_attr,
+        fields,
+        quote!(#builder_name<#(#struct_args,)* #(#prefilled),*>),
+        true,
+    );
+    let build = if struct_attr.validate.is_some() {
+        let error = builder_error::expand(target, struct_attr, &[])?;
+        let validation = get_validation(error_name, struct_attr);
+        quote! {
+            #error
+            #[allow(deprecated)]
+            impl #build_impl_generics #builder_name<#(#struct_args,)* #(#buildable),*> #where_clause {
+                #vis fn #build_fn(self) -> ::core::result::Result<#ty #ty_generics, #error_name> {
+                    #built
+                    #validation
+                    ::core::result::Result::Ok(built)
+                }
+            }
+        }
+    } else {
+        quote! {
+            #[allow(deprecated)]
+            impl #build_impl_generics #builder_name<#(#struct_args,)* #(#buildable),*> #where_clause {
+                #vis fn #build_fn(self) -> #ty #ty_generics {
+                    #built
+                    built
+                }
+            }
+        }
+    };
+    let builder_attrs = get_builder_attrs(struct_attr, false);
+    Ok(quote! {
+        #builder_attrs
+        #vis struct #builder_name #declared_generics #where_clause {
+            #(#members)*
+            __phantom: ::core::marker::PhantomData<fn() -> (#ty #ty_generics, #(#group_states,)*)>,
+        }
+        impl #builder_impl_generics #builder_name<#(#struct_args,)* #(#all_states),*> #where_clause {
+            #(#setters)*
+        }
+        #(#group_setters)*
+        #build
+        impl #impl_generics #ty #ty_generics #where_clause {
+            #vis fn #constructor() -> #builder_name<#(#struct_args,)* #(#unset),*> {
+                #builder_name {
+                    #(#initial_members)*
+                    __phantom: ::core::marker::PhantomData,
+                }
+            }
+        }
+        #prefill
+    })
+}
+
+/// The setter of a field in `group`, moving the group's `state` to `((),)`.
+/// Unless the group allows several of its fields to be set, the setter is only
+/// there while the state is still `()`.
+fn group_setter(
+    target: &BuilderTarget,
+    fields: &[FieldInfo],
+    field: &FieldInfo,
+    group: &Group,
+    state: Ident,
+    builder_generics: &Generics,
+    all_states: &[&Ident],
+) -> TokenStream {
+    let BuilderTarget {
+        generics,
+        builder_name,
+        vis,
+        ..
+    } = target;
+    let repeatable = group.rule == GroupRule::AtLeast;
+    let mut impl_generics = builder_generics.clone();
+    if !repeatable {
+        impl_generics.params = impl_generics
+            .params
+            .into_iter()
+            .filter(|param| !matches!(param, GenericParam::Type(ty) if ty.ident == state))
+            .collect();
+    }
+    let (impl_generics, _, _) = impl_generics.split_for_impl();
+    let struct_args = generic_args(generics);
+    let where_clause = &generics.where_clause;
+    let current = all_states.iter().map(|other| {
+        if **other == state && !repeatable {
+            quote!(())
+        } else {
+            quote!(#other)
+        }
+    });
+    let next = all_states.iter().map(|other| {
+        if **other == state {
+            quote!(((),))
+        } else {
+            quote!(#other)
+        }
+    });
+    let name = &field.name;
+    let setter_name = &field.setter_name;
+    let (param, value) = field.setter_arg("val", field.setter_ty());
+    let value = if field.setter_wraps_some() {
+        quote!(::core::option::Option::Some(#value))
+    } else {
+        value
+    };
+    let other_members = other_members(fields, field);
+    let cfgs = &field.cfgs;
+    quote_spanned! { field.span =>
+        #(#cfgs)*
+        impl #impl_generics #builder_name<#(#struct_args,)* #(#current),*> #where_clause {
+            #vis fn #setter_name(self, #param) -> #builder_name<#(#struct_args,)* #(#next),*> {
+                #builder_name {
+                    #name: #value,
+                    #(#other_members)*
+                    __phantom: ::core::marker::PhantomData,
+                }
+            }
+        }
+    }
+}
+
+/// The members of the builder other than that of `field`, moved out of `self`
+/// into the builder a setter returns.
+fn other_members<'a>(
+    fields: &'a [FieldInfo],
+    field: &'a FieldInfo,
+) -> impl Iterator<Item = TokenStream> + 'a {
+    fields
+        .iter()
+        .filter(move |other| other.name != field.name)
+        .map(|other| {
+            let name = &other.name;
+            let cfgs = &other.cfgs;
+            quote!(#(#cfgs)* #name: self.#name,)
+        })
+}
+
+/// The struct's own generic parameters as they appear in argument position.
+fn generic_args(generics: &Generics) -> Vec<TokenStream> {
+    generics
+        .params
+        .iter()
+        .map(|param| match param {
+            GenericParam::Type(ty) => {
+                let ident = &ty.ident;
+                quote!(#ident)
+            }
+            GenericParam::Lifetime(lifetime) => {
+                let lifetime = &lifetime.lifetime;
+                quote!(#lifetime)
+            }
+            GenericParam::Const(konst) => {
+                let ident = &konst.ident;
+                quote!(#ident)
+            }
+        })
+        .collect()
+}
@@ -0,0 +1,36 @@
+// With #[builder(typestate)] the builder tracks which required fields have
+// been set in its type. Every required field gets its own type parameter on
+// the builder, and build() is only implemented once all of them are set, so
+// it no longer needs to return a Result.
+//
+// Optional fields and repeated fields may be set in any state and in any
+// order relative to the required ones.
+
+use derive_builder::Builder;
+
+#[derive(Builder)]
+#[builder(typestate)]
+pub struct Command {
+    executable: String,
+    #[builder(each = "arg")]
+    args: Vec<String>,
+    env: Vec<String>,
+    current_dir: Option<String>,
+    retries: u32,
+}
+
+fn main() {
+    let command = Command::builder()
+        .arg("build".to_owned())
+        .executable("cargo".to_owned())
+        .current_dir("..".to_owned())
+        .arg("--release".to_owned())
+        .retries(3)
+        .build();
+
+    assert_eq!(command.executable, "cargo");
+    assert_eq!(command.args, vec!["build", "--release"]);
+    assert!(command.env.is_empty());
+    assert_eq!(command.current_dir.unwrap(), "..");
+    assert_eq!(command.retries, 3);
+}
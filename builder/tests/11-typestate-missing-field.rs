@@ -0,0 +1,19 @@
+// Forgetting a required field on a typestate builder is caught by the
+// compiler at the call to build(), instead of surfacing as an Err at runtime.
+
+use derive_builder::Builder;
+
+#[derive(Builder)]
+#[builder(typestate)]
+pub struct Command {
+    executable: String,
+    current_dir: Option<String>,
+    retries: u32,
+}
+
+fn main() {
+    let _command = Command::builder()
+        .executable("cargo".to_owned())
+        .current_dir("..".to_owned())
+        .build();
+}
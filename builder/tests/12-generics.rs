@@ -0,0 +1,72 @@
+// The builder carries over every type, lifetime and const parameter of the
+// struct it is derived on, together with the bounds and where clause.
+//
+// The builder's Default impl is written out by hand rather than derived, as
+// #[derive(Default)] would add a `T: Default` bound on every type parameter
+// even though an unset builder only ever holds None and empty vectors.
+
+use derive_builder::Builder;
+
+pub trait Transport {
+    fn scheme(&self) -> &'static str;
+}
+
+#[derive(Clone)]
+pub struct Tcp;
+
+impl Transport for Tcp {
+    fn scheme(&self) -> &'static str {
+        "tcp"
+    }
+}
+
+#[derive(Builder)]
+pub struct Conn<'a, T: Transport, const N: usize>
+where
+    T: Clone,
+{
+    host: &'a str,
+    transport: T,
+    #[builder(each = "backup")]
+    backups: Vec<T>,
+    window: [u8; N],
+    timeout: Option<u64>,
+}
+
+#[derive(Builder)]
+#[builder(typestate)]
+pub struct Listener<'a, T: Transport = Tcp>
+where
+    T: Clone,
+{
+    host: &'a str,
+    transport: T,
+    backlog: Option<u32>,
+}
+
+fn main() {
+    let host = String::from("localhost");
+    let conn = Conn::builder()
+        .host(&host)
+        .transport(Tcp)
+        .backup(Tcp)
+        .window([0; 4])
+        .build()
+        .unwrap();
+
+    assert_eq!(conn.host, "localhost");
+    assert_eq!(conn.transport.scheme(), "tcp");
+    assert_eq!(conn.backups.len(), 1);
+    assert_eq!(conn.window.len(), 4);
+    assert_eq!(conn.timeout, None);
+
+    let listener = Listener::builder()
+        .backlog(128)
+        .transport(Tcp)
+        .host(&host)
+        .build();
+
+    assert_eq!(listener.host, "localhost");
+    assert_eq!(listener.transport.scheme(), "tcp");
+    assert_eq!(listener.backlog, Some(128));
+}
@@ -0,0 +1,32 @@
+// build() reports a missing field through a typed error enum generated next
+// to the builder, named after it with an `Error` suffix. Every required field
+// gets its own `Missing<Field>` variant so callers can match on exactly which
+// field was left out instead of inspecting a boxed string.
+
+use derive_builder::Builder;
+
+#[derive(Builder)]
+pub struct Command {
+    executable: String,
+    current_dir: Option<String>,
+    retry_count: u32,
+}
+
+fn main() {
+    let err = Command::builder()
+        .executable("cargo".to_owned())
+        .build()
+        .err()
+        .unwrap();
+
+    assert_eq!(err, CommandBuilderError::MissingRetryCount);
+    assert_eq!(err.field_name(), Some("retry_count"));
+    assert_eq!(err.to_string(), "retry_count is not set");
+
+    let err: Box<dyn std::error::Error> = Box::new(err);
+    assert_eq!(err.to_string(), "retry_count is not set");
+
+    let err = CommandBuilderError::Validation("out of range".to_owned());
+    assert_eq!(err.field_name(), None);
+    assert_eq!(err.to_string(), "out of range");
+}
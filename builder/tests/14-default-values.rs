@@ -0,0 +1,66 @@
+// Fields marked #[builder(default)] fall back to Default::default() when their
+// setter is never called, and #[builder(default = "...")] evaluates the given
+// expression instead. The expression may refer to any field declared before
+// it by name, as fields are resolved in declaration order.
+//
+// A #[builder(default)] on the struct itself applies to every field.
+
+use derive_builder::Builder;
+
+#[derive(Builder)]
+pub struct Server {
+    host: String,
+    #[builder(default = "8080")]
+    port: u16,
+    #[builder(default = "format!(\"{}:{}\", host, port)")]
+    address: String,
+    #[builder(default)]
+    workers: usize,
+    #[builder(default = "Some(30)")]
+    timeout: Option<u64>,
+}
+
+#[derive(Builder)]
+#[builder(default)]
+pub struct Limits {
+    connections: u32,
+    #[builder(default = "connections * 2")]
+    requests: u32,
+}
+
+#[derive(Builder)]
+#[builder(typestate)]
+pub struct Client {
+    host: String,
+    #[builder(default = "443")]
+    port: u16,
+}
+
+fn main() {
+    let server = Server::builder().host("localhost".to_owned()).build().unwrap();
+    assert_eq!(server.port, 8080);
+    assert_eq!(server.address, "localhost:8080");
+    assert_eq!(server.workers, 0);
+    assert_eq!(server.timeout, Some(30));
+
+    let server = Server::builder()
+        .host("example.com".to_owned())
+        .port(80)
+        .workers(4)
+        .timeout(5)
+        .build()
+        .unwrap();
+    assert_eq!(server.address, "example.com:80");
+    assert_eq!(server.workers, 4);
+    assert_eq!(server.timeout, Some(5));
+
+    let limits = Limits::builder().build().unwrap();
+    assert_eq!(limits.connections, 0);
+    assert_eq!(limits.requests, 0);
+
+    let limits = Limits::builder().connections(10).build().unwrap();
+    assert_eq!(limits.requests, 20);
+
+    let client = Client::builder().host("example.com".to_owned()).build();
+    assert_eq!(client.port, 443);
+}
@@ -0,0 +1,102 @@
+// A #[builder(validate = path::to::function)] on the struct runs the given
+// function against the finished struct at the end of build(). An Err from it
+// is turned into the builder error's Validation variant using its Display
+// output, so invariants can live right next to the struct definition.
+//
+// Typestate builders normally have an infallible build(). With a validate hook
+// their build() returns a Result as well.
+//
+// The function is looked up where the struct is defined, so a field sharing
+// its name does not get in the way.
+
+use derive_builder::Builder;
+
+mod checks {
+    pub fn range(range: &super::Range) -> Result<(), String> {
+        if range.min <= range.max {
+            Ok(())
+        } else {
+            Err(format!("min {} is greater than max {}", range.min, range.max))
+        }
+    }
+}
+
+fn named(listener: &Listener) -> Result<(), &'static str> {
+    if listener.name.is_empty() {
+        Err("name must not be empty")
+    } else {
+        Ok(())
+    }
+}
+
+#[derive(Builder)]
+#[builder(validate = checks::range)]
+pub struct Range {
+    min: u32,
+    max: u32,
+}
+
+#[derive(Builder)]
+#[builder(typestate, validate = named)]
+pub struct Listener {
+    name: String,
+    port: Option<u16>,
+}
+
+fn check(sample: &Sample) -> Result<(), &'static str> {
+    if sample.check % 2 == 0 {
+        Ok(())
+    } else {
+        Err("check must be even")
+    }
+}
+
+#[derive(Builder)]
+#[builder(validate = check)]
+pub struct Sample {
+    check: u32,
+}
+
+#[derive(Builder)]
+#[builder(typestate, validate = check_token)]
+pub struct Token {
+    check_token: String,
+}
+
+fn check_token(token: &Token) -> Result<(), &'static str> {
+    if token.check_token.is_empty() {
+        Err("token must not be empty")
+    } else {
+        Ok(())
+    }
+}
+
+fn main() {
+    let range = Range::builder().min(1).max(5).build().unwrap();
+    assert_eq!((range.min, range.max), (1, 5));
+
+    let err = Range::builder().min(5).max(1).build().err().unwrap();
+    assert_eq!(
+        err,
+        RangeBuilderError::Validation("min 5 is greater than max 1".to_owned())
+    );
+
+    let err = Range::builder().min(5).build().err().unwrap();
+    assert_eq!(err, RangeBuilderError::MissingMax);
+
+    let listener = Listener::builder().name("http".to_owned()).build().unwrap();
+    assert_eq!(listener.name, "http");
+    assert_eq!(listener.port, None);
+
+    let err = Listener::builder()
+        .name(String::new())
+        .port(80)
+        .build()
+        .err()
+        .unwrap();
+    assert_eq!(err.to_string(), "name must not be empty");
+
+    assert_eq!(Sample::builder().check(2).build().unwrap().check, 2);
+    assert!(Sample::builder().check(3).build().is_err());
+    assert!(Token::builder().check_token(String::new()).build().is_err());
+}
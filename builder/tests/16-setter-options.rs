@@ -0,0 +1,60 @@
+// Setters can be customized per field through #[builder(setter(...))]:
+//
+//   - `into` makes the setter generic over `impl Into<T>`, so a &str can be
+//     passed straight to a String field.
+//   - `strip_option = false` makes the setter of an Option<T> field take the
+//     Option<T> itself instead of a bare T.
+//   - `name = "..."` renames the setter.
+//
+// A #[builder(setter(prefix = "..."))] on the struct prefixes every setter
+// that was not explicitly renamed.
+
+use derive_builder::Builder;
+
+#[derive(Builder)]
+pub struct Command {
+    #[builder(setter(into))]
+    executable: String,
+    #[builder(each = "arg", setter(into))]
+    args: Vec<String>,
+    #[builder(setter(strip_option = false))]
+    current_dir: Option<String>,
+    #[builder(setter(name = "retries", into))]
+    retry_count: u64,
+}
+
+#[derive(Builder)]
+#[builder(typestate, setter(prefix = "with_"))]
+pub struct Request {
+    #[builder(setter(into))]
+    url: String,
+    #[builder(setter(name = "timeout"))]
+    timeout_secs: Option<u64>,
+    #[builder(default)]
+    verbose: bool,
+}
+
+fn main() {
+    let command = Command::builder()
+        .executable("cargo")
+        .arg("build")
+        .current_dir(Some("..".to_owned()))
+        .retries(3u8)
+        .build()
+        .unwrap();
+
+    assert_eq!(command.executable, "cargo");
+    assert_eq!(command.args, vec!["build"]);
+    assert_eq!(command.current_dir.as_deref(), Some(".."));
+    assert_eq!(command.retry_count, 3);
+
+    let request = Request::builder()
+        .with_url("http://localhost")
+        .timeout(5)
+        .with_verbose(true)
+        .build();
+
+    assert_eq!(request.url, "http://localhost");
+    assert_eq!(request.timeout_secs, Some(5));
+    assert!(request.verbose);
+}
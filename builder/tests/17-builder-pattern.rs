@@ -0,0 +1,59 @@
+// #[builder(pattern = "...")] picks how setters and build() take the builder.
+//
+//   - "mutable", the default, chains through `&mut Self` and clones every
+//     field out of the builder in build().
+//   - "owned" setters take and return the builder by value and build(self)
+//     moves the fields out, so field types do not have to implement Clone.
+//   - "immutable" setters take `&self` and return an updated copy, leaving the
+//     original builder untouched, so partially built builders can be reused.
+
+use derive_builder::Builder;
+
+pub struct Socket {
+    fd: i32,
+}
+
+#[derive(Builder)]
+#[builder(pattern = "owned")]
+pub struct Connection {
+    socket: Socket,
+    #[builder(each = "buffer")]
+    buffers: Vec<Vec<u8>>,
+    peer: Option<Socket>,
+}
+
+#[derive(Builder)]
+#[builder(pattern = "immutable")]
+pub struct Command {
+    executable: String,
+    #[builder(each = "arg")]
+    args: Vec<String>,
+}
+
+#[derive(Builder)]
+#[builder(pattern = "mutable")]
+pub struct Endpoint {
+    host: String,
+}
+
+fn main() {
+    let connection = Connection::builder()
+        .socket(Socket { fd: 3 })
+        .buffer(vec![0; 1024])
+        .build()
+        .unwrap();
+    assert_eq!(connection.socket.fd, 3);
+    assert_eq!(connection.buffers.len(), 1);
+    assert!(connection.peer.is_none());
+
+    let cargo = Command::builder().executable("cargo".to_owned());
+    let build = cargo.arg("build".to_owned()).build().unwrap();
+    let test = cargo.arg("test".to_owned()).build().unwrap();
+    assert_eq!(build.args, vec!["build"]);
+    assert_eq!(test.args, vec!["test"]);
+    assert!(cargo.build().unwrap().args.is_empty());
+
+    let mut builder = Endpoint::builder();
+    builder.host("localhost".to_owned());
+    assert_eq!(builder.build().unwrap().host, "localhost");
+}
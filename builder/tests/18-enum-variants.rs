@@ -0,0 +1,78 @@
+// Deriving Builder on an enum generates one builder per variant. The builder
+// for `Request::Get { .. }` is called RequestGetBuilder, is created through
+// `Request::get_builder()` and its build() returns a `Request::Get { .. }`.
+// Acronyms are kept together, so `Request::HTTPRedirect` is created through
+// `Request::http_redirect_builder()`.
+//
+// Variant fields support the same attributes as struct fields. A
+// #[builder(...)] on the enum applies to the builders of all of its variants,
+// and one on a variant to that variant's builder only.
+
+use derive_builder::Builder;
+
+#[derive(Builder, Debug, PartialEq)]
+pub enum Request<B: Clone + Default> {
+    Get {
+        url: String,
+        #[builder(each = "header")]
+        headers: Vec<String>,
+        timeout: Option<u64>,
+    },
+    #[builder(default)]
+    PostForm {
+        url: String,
+        body: B,
+    },
+    #[builder(typestate)]
+    Delete {
+        url: String,
+    },
+    Ping,
+    HTTPRedirect {
+        location: String,
+    },
+}
+
+fn main() {
+    let get = Request::<()>::get_builder()
+        .url("/".to_owned())
+        .header("Accept: */*".to_owned())
+        .build()
+        .unwrap();
+    assert_eq!(
+        get,
+        Request::Get {
+            url: "/".to_owned(),
+            headers: vec!["Accept: */*".to_owned()],
+            timeout: None,
+        }
+    );
+
+    let err = Request::<()>::get_builder().build().err().unwrap();
+    assert_eq!(err, RequestGetBuilderError::MissingUrl);
+
+    let post = Request::post_form_builder().body(vec![1u8]).build().unwrap();
+    assert_eq!(
+        post,
+        Request::PostForm {
+            url: String::new(),
+            body: vec![1],
+        }
+    );
+
+    let delete = Request::<()>::delete_builder().url("/1".to_owned()).build();
+    assert_eq!(delete, Request::Delete { url: "/1".to_owned() });
+
+    assert_eq!(Request::<()>::ping_builder().build().unwrap(), Request::Ping);
+
+    let redirect = Request::<()>::http_redirect_builder()
+        .location("/login".to_owned())
+        .build()
+        .unwrap();
+    assert_eq!(
+        redirect,
+        Request::HTTPRedirect {
+            location: "/login".to_owned(),
+        }
+    );
+}
@@ -0,0 +1,45 @@
+// Tuple structs get one setter per position, called _0, _1, ... unless a
+// field is given a name through #[builder(name = "...")]. The single field of
+// a newtype gets a setter called `value`.
+
+use derive_builder::Builder;
+
+#[derive(Builder, Debug, PartialEq)]
+pub struct Rgb(#[builder(name = "red")] u8, #[builder(name = "green")] u8, u8);
+
+#[derive(Builder, Clone, Debug, PartialEq)]
+pub struct Meters(f64);
+
+#[derive(Builder, Debug, PartialEq)]
+#[builder(typestate)]
+pub struct Pair(String, #[builder(default = "_0.len()")] usize);
+
+#[derive(Builder, Debug, PartialEq)]
+pub enum Shape {
+    Circle(Meters),
+    Line(Meters, Meters),
+}
+
+fn main() {
+    let rgb = Rgb::builder().red(255).green(128)._2(0).build().unwrap();
+    assert_eq!(rgb, Rgb(255, 128, 0));
+
+    let err = Rgb::builder().red(255).green(128).build().err().unwrap();
+    assert_eq!(err, RgbBuilderError::Missing2);
+
+    let length = Meters::builder().value(1.5).build().unwrap();
+    assert_eq!(length, Meters(1.5));
+
+    let pair = Pair::builder()._0("four".to_owned()).build();
+    assert_eq!(pair, Pair("four".to_owned(), 4));
+
+    let circle = Shape::circle_builder().value(Meters(2.0)).build().unwrap();
+    assert_eq!(circle, Shape::Circle(Meters(2.0)));
+
+    let line = Shape::line_builder()
+        ._0(Meters(0.0))
+        ._1(Meters(3.0))
+        .build()
+        .unwrap();
+    assert_eq!(line, Shape::Line(Meters(0.0), Meters(3.0)));
+}
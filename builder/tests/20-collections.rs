@@ -0,0 +1,69 @@
+// #[builder(each = "...")] is not limited to Vec. Sets, VecDeque and friends
+// get a one-at-a-time setter taking an item, and HashMap/BTreeMap get one
+// taking a key and a value. Any other collection implementing Default and
+// Extend can be used by naming its item type with #[builder(item = ...)].
+//
+// Every `each` field also gets an extend_<field> method taking anything that
+// can be iterated over to add many items at once.
+
+use derive_builder::Builder;
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet, VecDeque};
+
+#[derive(Clone, Default, Debug, PartialEq)]
+pub struct Path(String);
+
+impl Extend<&'static str> for Path {
+    fn extend<I: IntoIterator<Item = &'static str>>(&mut self, iter: I) {
+        for segment in iter {
+            self.0.push('/');
+            self.0.push_str(segment);
+        }
+    }
+}
+
+#[derive(Builder)]
+pub struct Request {
+    #[builder(each = "header", setter(into))]
+    headers: HashMap<String, String>,
+    #[builder(each = "param")]
+    params: BTreeMap<&'static str, u32>,
+    #[builder(each = "tag")]
+    tags: HashSet<String>,
+    #[builder(each = "flag")]
+    flags: BTreeSet<u8>,
+    #[builder(each = "retry")]
+    retries: VecDeque<u64>,
+    #[builder(each = "segment", item = &'static str)]
+    path: Path,
+    #[builder(each = "arg")]
+    args: Vec<String>,
+}
+
+fn main() {
+    let request = Request::builder()
+        .header("Accept", "*/*")
+        .param("page", 2)
+        .extend_params([("limit", 10)])
+        .tag("a".to_owned())
+        .tag("a".to_owned())
+        .flag(3)
+        .flag(1)
+        .retry(10)
+        .extend_retries(vec![20, 40])
+        .segment("api")
+        .segment("v1")
+        .extend_args(["--release".to_owned()])
+        .build()
+        .unwrap();
+
+    assert_eq!(request.headers["Accept"], "*/*");
+    assert_eq!(
+        request.params.into_iter().collect::<Vec<_>>(),
+        vec![("limit", 10), ("page", 2)]
+    );
+    assert_eq!(request.tags.len(), 1);
+    assert_eq!(request.flags.into_iter().collect::<Vec<_>>(), vec![1, 3]);
+    assert_eq!(request.retries, VecDeque::from(vec![10, 20, 40]));
+    assert_eq!(request.path, Path("/api/v1".to_owned()));
+    assert_eq!(request.args, vec!["--release"]);
+}
@@ -0,0 +1,47 @@
+// Option and Vec fields are recognised when written as fully qualified paths
+// into std, core or alloc, as code generators tend to emit them.
+//
+// A type alias hides the Option or Vec behind it from the macro entirely, so
+// such fields opt in with #[builder(optional)] or #[builder(repeated)].
+
+extern crate alloc;
+
+use derive_builder::Builder;
+
+type MaybePort = Option<u16>;
+type Names = Vec<String>;
+
+#[derive(Builder)]
+pub struct Service {
+    name: ::std::string::String,
+    port: std::option::Option<u16>,
+    host: ::core::option::Option<String>,
+    #[builder(each = "alias")]
+    aliases: ::std::vec::Vec<String>,
+    #[builder(each = "label")]
+    labels: alloc::collections::BTreeMap<String, String>,
+    #[builder(optional)]
+    admin_port: MaybePort,
+    #[builder(repeated, each = "owner")]
+    owners: Names,
+}
+
+fn main() {
+    let service = Service::builder()
+        .name("api".to_owned())
+        .port(8080)
+        .alias("gateway".to_owned())
+        .label("team".to_owned(), "core".to_owned())
+        .admin_port(9090)
+        .owner("ops".to_owned())
+        .build()
+        .unwrap();
+
+    assert_eq!(service.name, "api");
+    assert_eq!(service.port, Some(8080));
+    assert_eq!(service.host, None);
+    assert_eq!(service.aliases, vec!["gateway"]);
+    assert_eq!(service.labels["team"], "core");
+    assert_eq!(service.admin_port, Some(9090));
+    assert_eq!(service.owners, vec!["ops"]);
+}
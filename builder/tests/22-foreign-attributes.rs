@@ -0,0 +1,60 @@
+// Fields carry plenty of attributes that have nothing to do with the builder:
+// doc comments, lints, other derives' helper attributes. Only #[builder(...)]
+// attributes are looked at, wherever they appear among the others, and a field
+// may have several of them whose options are combined.
+//
+// Using a #[deprecated] field is left to the caller to warn about, so the
+// builder's own code does not raise a warning for every use it makes of it.
+
+#![deny(warnings)]
+
+use derive_builder::Builder;
+
+#[derive(Builder)]
+#[builder(to_builder)]
+pub struct Command {
+    /// The program to run.
+    #[allow(dead_code)]
+    executable: String,
+    /// Arguments passed to the program.
+    #[doc = "One at a time through `arg`."]
+    #[builder(each = "arg")]
+    #[allow(clippy::all)]
+    #[builder(setter(into))]
+    args: Vec<String>,
+    #[deprecated = "use `args`"]
+    #[builder(default)]
+    #[allow(unused)]
+    retries: u32,
+}
+
+#[derive(Builder)]
+#[builder(typestate)]
+pub struct Job {
+    #[deprecated = "use `command`"]
+    name: String,
+    command: Option<String>,
+}
+
+fn main() {
+    #[allow(deprecated)]
+    let command = Command::builder()
+        .executable("cargo".to_owned())
+        .arg("build")
+        .build()
+        .unwrap();
+
+    assert_eq!(command.executable, "cargo");
+    assert_eq!(command.args, vec!["build"]);
+    #[allow(deprecated)]
+    let retries = command.retries;
+    assert_eq!(retries, 0);
+    let rebuilt = CommandBuilder::from(command.to_builder().build().unwrap())
+        .build()
+        .unwrap();
+    assert_eq!(rebuilt.executable, "cargo");
+
+    #[allow(deprecated)]
+    let job = Job::builder().name("ci".to_owned()).build();
+    assert_eq!(job.command, None);
+}
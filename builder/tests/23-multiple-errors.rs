@@ -0,0 +1,32 @@
+// Every mistake in the builder attributes is reported in the same compile
+// pass, rather than only the first one so that fixing five typos takes five
+// rounds of compiling.
+//
+// The builder is still generated next to the errors, leaving out only the
+// options that were wrong, so the code using it doesn't add a wall of "no
+// method named ..." errors on top of the ones that matter.
+
+use derive_builder::Builder;
+
+#[derive(Builder)]
+#[builder(patern = "owned", setter(prefx = "with_"))]
+pub struct Command {
+    #[builder(setter(int))]
+    executable: String,
+    #[builder(each = "arg", defualt)]
+    args: Vec<String>,
+    #[builder(default = 3, name = "retries")]
+    retry_count: u32,
+    #[builder(each = "var")]
+    env: String,
+}
+
+fn main() {
+    let _command = Command::builder()
+        .executable("cargo".to_owned())
+        .arg("build".to_owned())
+        .retry_count(1)
+        .env("RUST_LOG=debug".to_owned())
+        .build()
+        .unwrap();
+}
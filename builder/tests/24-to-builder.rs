@@ -0,0 +1,66 @@
+// An existing value can be turned back into a builder with every field
+// already set, so that a modified copy only needs setters for what changes.
+//
+// `From<Command>` is implemented for every builder of a struct and consumes
+// the value. With #[builder(to_builder)] there is also a `to_builder(&self)`
+// which clones the fields instead, so they all have to implement Clone.
+
+use derive_builder::Builder;
+
+#[derive(Builder, Debug, PartialEq)]
+#[builder(to_builder)]
+pub struct Command {
+    executable: String,
+    #[builder(each = "arg")]
+    args: Vec<String>,
+    current_dir: Option<String>,
+    #[builder(default = 3)]
+    retries: u32,
+}
+
+#[derive(Builder, Debug, PartialEq)]
+#[builder(typestate)]
+pub struct Point<T> {
+    x: T,
+    y: T,
+    label: Option<String>,
+}
+
+#[derive(Builder, Debug, PartialEq)]
+#[builder(to_builder, pattern = "owned")]
+pub struct Pair(u32, String);
+
+fn main() {
+    let base = Command::builder()
+        .executable("cargo".to_owned())
+        .arg("build".to_owned())
+        .current_dir("..".to_owned())
+        .retries(1)
+        .build()
+        .unwrap();
+
+    let release = base.to_builder().arg("--release".to_owned()).build().unwrap();
+    assert_eq!(release.executable, "cargo");
+    assert_eq!(release.args, vec!["build", "--release"]);
+    assert_eq!(release.current_dir.as_deref(), Some(".."));
+    assert_eq!(release.retries, 1);
+
+    let rebuilt = CommandBuilder::from(base).build().unwrap();
+    assert_eq!(rebuilt.args, vec!["build"]);
+
+    let point = Point::builder().x(1).y(2).build();
+    let moved = PointBuilder::from(point).x(5).label("moved".to_owned()).build();
+    assert_eq!(
+        moved,
+        Point {
+            x: 5,
+            y: 2,
+            label: Some("moved".to_owned()),
+        }
+    );
+
+    let pair = Pair(1, "one".to_owned());
+    let copy = pair.to_builder()._0(2).build().unwrap();
+    assert_eq!(copy, Pair(2, "one".to_owned()));
+    assert_eq!(PairBuilder::from(pair).build().unwrap(), Pair(1, "one".to_owned()));
+}
@@ -0,0 +1,118 @@
+// Layered configuration, such as defaults overridden by a config file
+// overridden by command line flags, can be written as partially filled
+// builders merged on top of each other with #[builder(merge)].
+//
+// `merge` takes another builder and `merge_from` borrows one. Fields set in
+// the other builder win. Collections are appended to by default, or replaced
+// by the other builder's items, if it has any, with
+// #[builder(merge = "replace")].
+//
+// Sub-builders are merged field by field rather than replaced, which needs
+// #[builder(merge)] on the inner struct as well.
+
+use derive_builder::Builder;
+
+#[derive(Builder, Debug, PartialEq)]
+#[builder(merge)]
+pub struct Config {
+    host: String,
+    port: u16,
+    user: Option<String>,
+    #[builder(each = "include")]
+    includes: Vec<String>,
+    #[builder(each = "feature", merge = "replace")]
+    features: Vec<String>,
+}
+
+#[derive(Builder, Debug, PartialEq)]
+#[builder(merge, pattern = "owned")]
+pub struct Limits {
+    #[builder(default = 10)]
+    connections: u32,
+    timeout: Option<u64>,
+}
+
+#[derive(Builder, Debug, PartialEq)]
+#[builder(merge)]
+pub struct Tls {
+    cert: String,
+    verify: Option<bool>,
+}
+
+#[derive(Builder, Debug, PartialEq)]
+#[builder(merge)]
+pub struct Listener {
+    #[builder(sub_builder)]
+    tls: Tls,
+}
+
+fn main() {
+    let mut defaults = Listener::builder();
+    defaults.tls().verify(true);
+    let mut flags = Listener::builder();
+    flags.tls().cert("server.pem".to_owned());
+    let mut borrowed = Listener::builder();
+    borrowed.merge_from(&defaults).merge_from(&flags);
+    defaults.merge(flags);
+    for mut merged in [defaults, borrowed] {
+        assert_eq!(
+            merged.build().unwrap().tls,
+            Tls {
+                cert: "server.pem".to_owned(),
+                verify: Some(true),
+            }
+        );
+    }
+
+    let mut defaults = Config::builder();
+    defaults
+        .host("localhost".to_owned())
+        .port(80)
+        .include("base.conf".to_owned())
+        .feature("gzip".to_owned());
+
+    let mut file = Config::builder();
+    file.port(8080)
+        .user("www".to_owned())
+        .include("site.conf".to_owned());
+
+    let mut flags = Config::builder();
+    flags.feature("tls".to_owned());
+
+    let config = [file, flags]
+        .into_iter()
+        .fold(defaults, |mut config, layer| {
+            config.merge(layer);
+            config
+        })
+        .build()
+        .unwrap();
+
+    assert_eq!(
+        config,
+        Config {
+            host: "localhost".to_owned(),
+            port: 8080,
+            user: Some("www".to_owned()),
+            includes: vec!["base.conf".to_owned(), "site.conf".to_owned()],
+            features: vec!["tls".to_owned()],
+        }
+    );
+
+    let mut base = Config::builder();
+    base.host("example.com".to_owned()).port(443);
+    let mut copy = Config::builder();
+    copy.merge_from(&base).merge_from(&base);
+    assert_eq!(copy.build().unwrap().host, "example.com");
+    assert_eq!(base.build().unwrap().port, 443);
+
+    let mut limits = Limits::builder().timeout(30);
+    limits.merge(Limits::builder().connections(5));
+    assert_eq!(
+        limits.build().unwrap(),
+        Limits {
+            connections: 5,
+            timeout: Some(30),
+        }
+    );
+}
@@ -0,0 +1,60 @@
+// Fields marked #[builder(env = "...")] can be read from environment
+// variables, parsed with FromStr, by `fill_from_env()` on an existing builder
+// or `from_env()` for a new one. Fields that are already set are left alone,
+// so setters called before filling take precedence over the environment.
+//
+// A variable that fails to parse is reported through an `Env` variant of the
+// builder's error, naming both the field and the variable.
+
+use derive_builder::Builder;
+
+#[derive(Builder, Debug)]
+pub struct Daemon {
+    #[builder(env = "DAEMON_HOST")]
+    host: String,
+    #[builder(env = "DAEMON_PORT")]
+    port: u16,
+    #[builder(env = "DAEMON_WORKERS")]
+    workers: Option<usize>,
+    #[builder(env = "DAEMON_VERBOSE", default)]
+    verbose: bool,
+}
+
+fn main() {
+    std::env::set_var("DAEMON_HOST", "0.0.0.0");
+    std::env::set_var("DAEMON_PORT", "8080");
+    std::env::remove_var("DAEMON_WORKERS");
+    std::env::remove_var("DAEMON_VERBOSE");
+
+    let daemon = DaemonBuilder::from_env().unwrap().build().unwrap();
+    assert_eq!(daemon.host, "0.0.0.0");
+    assert_eq!(daemon.port, 8080);
+    assert_eq!(daemon.workers, None);
+    assert!(!daemon.verbose);
+
+    std::env::set_var("DAEMON_WORKERS", "4");
+    let daemon = Daemon::builder()
+        .host("localhost".to_owned())
+        .fill_from_env()
+        .unwrap()
+        .build()
+        .unwrap();
+    assert_eq!(daemon.host, "localhost");
+    assert_eq!(daemon.workers, Some(4));
+
+    std::env::set_var("DAEMON_PORT", "eighty");
+    let err = DaemonBuilder::from_env().err().unwrap();
+    assert_eq!(err.field_name(), Some("port"));
+    assert_eq!(
+        err.to_string(),
+        "port: invalid value in DAEMON_PORT: invalid digit found in string"
+    );
+    assert!(matches!(
+        err,
+        DaemonBuilderError::Env {
+            field: "port",
+            variable: "DAEMON_PORT",
+            ..
+        }
+    ));
+}
@@ -0,0 +1,43 @@
+// Some fields are never meant to be set by callers. With #[builder(skip)] a
+// field gets no setter and starts out as Default::default(), and with
+// #[builder(compute = "...")] it is filled in by build() from an expression
+// that can use every other field of the struct by name.
+
+use derive_builder::Builder;
+use std::collections::HashMap;
+
+#[derive(Builder, Debug)]
+pub struct Endpoint {
+    host: String,
+    port: u16,
+    #[builder(compute = "format!(\"{}:{}\", host, port)")]
+    address: String,
+    #[builder(skip)]
+    cache: HashMap<String, Vec<u8>>,
+    #[builder(skip)]
+    requests: u64,
+}
+
+#[derive(Builder, Debug)]
+#[builder(typestate)]
+pub struct Rect {
+    #[builder(compute = "width * height")]
+    area: u32,
+    width: u32,
+    height: u32,
+}
+
+fn main() {
+    let endpoint = Endpoint::builder()
+        .host("localhost".to_owned())
+        .port(8080)
+        .build()
+        .unwrap();
+
+    assert_eq!(endpoint.address, "localhost:8080");
+    assert!(endpoint.cache.is_empty());
+    assert_eq!(endpoint.requests, 0);
+
+    let rect = Rect::builder().width(3).height(4).build();
+    assert_eq!(rect.area, 12);
+}
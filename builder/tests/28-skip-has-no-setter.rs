@@ -0,0 +1,17 @@
+// A skipped field has no setter, so trying to set it is a compile error.
+
+use derive_builder::Builder;
+
+#[derive(Builder)]
+pub struct Counter {
+    name: String,
+    #[builder(skip)]
+    hits: u64,
+}
+
+fn main() {
+    let _counter = Counter::builder()
+        .name("requests".to_owned())
+        .hits(10)
+        .build();
+}
@@ -0,0 +1,66 @@
+// The builder, its error type and all of their methods are as visible as the
+// struct they build, so a `pub struct` in a module or library can be built
+// from outside of it. #[builder(vis = "...")] picks a different visibility.
+//
+// #[builder(derive(...))] derives extra traits for the builder, such as
+// Debug to print a half built one, and #[builder(struct_attr(...))] passes
+// any other attribute through to the builder struct.
+
+mod config {
+    use derive_builder::Builder;
+
+    #[derive(Builder, Debug, PartialEq)]
+    #[builder(derive(Debug, Clone, PartialEq))]
+    pub struct Server {
+        host: String,
+        port: u16,
+        #[builder(each = "alias")]
+        aliases: Vec<String>,
+    }
+
+    #[derive(Builder)]
+    #[builder(vis = "pub(crate)", struct_attr(must_use))]
+    pub struct Client {
+        pub(crate) timeout: u64,
+    }
+
+    #[derive(Builder, Debug)]
+    #[builder(typestate, derive(Debug))]
+    pub struct Point {
+        pub x: i32,
+        pub y: i32,
+    }
+
+    #[derive(Builder, Debug, PartialEq)]
+    #[builder(pattern = "immutable", derive(Clone, Debug))]
+    pub enum Shape {
+        Circle { radius: u32 },
+    }
+}
+
+use config::{ClientBuilder, Point, Server, ServerBuilder, ServerBuilderError, Shape};
+
+fn main() {
+    let mut builder: ServerBuilder = Server::builder();
+    builder.host("localhost".to_owned()).alias("local".to_owned());
+
+    let mut half_built = builder.clone();
+    assert_eq!(half_built, builder);
+    assert!(format!("{:?}", half_built).contains("localhost"));
+
+    let err: ServerBuilderError = half_built.build().unwrap_err();
+    assert_eq!(err.field_name(), Some("port"));
+
+    let server = builder.port(80).build().unwrap();
+    assert!(format!("{:?}", server).contains("port: 80"));
+
+    let client = ClientBuilder::default().timeout(30).build().unwrap();
+    assert_eq!(client.timeout, 30);
+
+    let point = Point::builder().x(1);
+    assert!(format!("{:?}", point).contains("x: (1,)"));
+    assert_eq!(point.y(2).build().x, 1);
+
+    let shape = Shape::circle_builder().radius(2);
+    assert_eq!(shape.clone().build().unwrap(), Shape::Circle { radius: 2 });
+}
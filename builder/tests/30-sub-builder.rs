@@ -0,0 +1,142 @@
+// A field whose type derives Builder itself can be marked
+// #[builder(sub_builder)]. Instead of a setter taking the finished value, the
+// outer builder then has a method returning the inner builder, created the
+// first time it is asked for, and build() builds the inner value too.
+//
+// Errors from the inner builder are wrapped in an `Invalid<Field>` variant of
+// the outer error, and read as a path to the offending field.
+//
+// The inner builder may use any pattern and name its build() anything. An
+// owned inner builder is consumed by building the outer one, like it would be
+// by building it on its own. An immutable outer builder clones its fields,
+// though, the inner builder among them, so unless that one is immutable too
+// it needs #[builder(derive(Clone))] on the inner struct. A typestate builder
+// cannot be an inner builder at all.
+
+use derive_builder::Builder;
+
+#[derive(Builder, Debug, PartialEq)]
+pub struct TlsConfig {
+    cert_path: String,
+    #[builder(default)]
+    verify: bool,
+}
+
+#[derive(Builder, Debug, PartialEq)]
+pub struct Server {
+    host: String,
+    #[builder(sub_builder)]
+    tls: TlsConfig,
+}
+
+#[derive(Builder, Debug)]
+pub struct Cluster {
+    #[builder(sub_builder)]
+    primary: Server,
+}
+
+#[derive(Builder, Debug)]
+#[builder(pattern = "owned")]
+pub struct Proxy {
+    #[builder(sub_builder)]
+    upstream: TlsConfig,
+}
+
+#[derive(Builder, Debug)]
+#[builder(pattern = "owned", build_fn = "finish")]
+pub struct Credentials {
+    user: String,
+}
+
+#[derive(Builder, Debug)]
+pub struct Client {
+    #[builder(sub_builder)]
+    credentials: Credentials,
+    #[builder(sub_builder)]
+    tls: TlsConfig,
+}
+
+#[derive(Builder, Debug)]
+pub struct Timeouts {
+    #[builder(parse_with = "str::parse")]
+    connect_ms: u32,
+}
+
+#[derive(Builder, Debug)]
+pub struct Pool {
+    #[builder(sub_builder)]
+    timeouts: Timeouts,
+}
+
+#[derive(Builder, Debug)]
+#[builder(derive(Clone))]
+pub struct Account {
+    name: String,
+}
+
+#[derive(Builder, Debug)]
+#[builder(pattern = "immutable")]
+pub struct Profile {
+    #[builder(sub_builder)]
+    account: Account,
+}
+
+fn main() {
+    let mut builder = Server::builder();
+    builder.host("example.com".to_owned());
+    builder.tls().cert_path("/etc/cert.pem".to_owned());
+    builder.tls().verify(true);
+    let server = builder.build().unwrap();
+    assert_eq!(
+        server.tls,
+        TlsConfig {
+            cert_path: "/etc/cert.pem".to_owned(),
+            verify: true,
+        }
+    );
+
+    let err = Server::builder()
+        .host("example.com".to_owned())
+        .build()
+        .unwrap_err();
+    assert_eq!(
+        err,
+        ServerBuilderError::InvalidTls(TlsConfigBuilderError::MissingCertPath)
+    );
+    assert_eq!(err.field_name(), Some("tls"));
+    assert_eq!(err.to_string(), "tls.cert_path is not set");
+
+    let mut cluster = Cluster::builder();
+    cluster.primary().host("a".to_owned());
+    let err = cluster.build().unwrap_err();
+    assert_eq!(err.to_string(), "primary.tls.cert_path is not set");
+
+    let mut proxy = Proxy::builder();
+    proxy.upstream().cert_path("/etc/proxy.pem".to_owned());
+    assert_eq!(proxy.build().unwrap().upstream.cert_path, "/etc/proxy.pem");
+
+    let mut client = Client::builder();
+    *client.credentials() = CredentialsBuilder::default().user("admin".to_owned());
+    client.tls().cert_path("/etc/client.pem".to_owned());
+    let built = client.build().unwrap();
+    assert_eq!(built.credentials.user, "admin");
+    assert_eq!(built.tls.cert_path, "/etc/client.pem");
+    assert_eq!(
+        client.build().unwrap_err(),
+        ClientBuilderError::InvalidCredentials(CredentialsBuilderError::MissingUser)
+    );
+
+    let mut pool = Pool::builder();
+    pool.timeouts().parse_connect_ms("soon");
+    assert_eq!(
+        pool.build().unwrap_err().to_string(),
+        "timeouts.connect_ms: invalid value: invalid digit found in string"
+    );
+
+    let mut profile = Profile::builder();
+    profile.account().name("guest".to_owned());
+    assert_eq!(profile.build().unwrap().account.name, "guest");
+
+    let rebuilt = ServerBuilder::from(server).build().unwrap();
+    assert!(rebuilt.tls.verify);
+}
@@ -0,0 +1,61 @@
+// #[builder(no_std)] makes the generated code use only `core` and `alloc`, for
+// crates that are #![no_std]. The error type still implements
+// core::error::Error, the same trait as std::error::Error, which needs Rust
+// 1.81. Builders without the attribute only need std::error::Error.
+//
+// This crate links std under another name, so any leftover `::std` path in the
+// generated code fails to resolve.
+
+#![no_std]
+
+extern crate alloc;
+extern crate std as rust_std;
+
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+use derive_builder::Builder;
+
+fn check_name(device: &Device) -> Result<(), &'static str> {
+    if device.name.is_empty() {
+        Err("name is empty")
+    } else {
+        Ok(())
+    }
+}
+
+#[derive(Builder, Debug)]
+#[builder(no_std, validate = check_name)]
+pub struct Device {
+    name: String,
+    #[builder(each = "register")]
+    registers: Vec<u32>,
+    baud_rate: Option<u32>,
+    #[builder(default = 1)]
+    retries: u8,
+}
+
+#[derive(Builder)]
+#[builder(no_std, typestate)]
+pub struct Pin {
+    number: u8,
+}
+
+fn main() {
+    let device = Device::builder()
+        .name("uart0".to_string())
+        .register(0x10)
+        .build()
+        .unwrap();
+    assert_eq!(device.registers, [0x10]);
+    assert_eq!(device.baud_rate, None);
+    assert_eq!(device.retries, 1);
+
+    let err = Device::builder().name(String::new()).build().unwrap_err();
+    assert_eq!(err, DeviceBuilderError::Validation("name is empty".to_string()));
+    assert_eq!(err.to_string(), "name is empty");
+    let err: &dyn core::error::Error = &err;
+    assert_eq!(err.to_string(), "name is empty");
+
+    assert_eq!(Pin::builder().number(13).build().number, 13);
+    let _ = rust_std::process::id();
+}
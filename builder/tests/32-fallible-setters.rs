@@ -0,0 +1,94 @@
+// Fields marked #[builder(try_setter)] get a `try_<field>` setter next to the
+// usual one, accepting anything that converts into the field's type with
+// TryInto and returning the conversion's error instead of the builder when it
+// fails.
+//
+// Fields marked #[builder(parse_with = path)] get a `parse_<field>` setter
+// taking a `&str` and running it through `path`, a `fn(&str) -> Result<T, E>`
+// where `E` implements Display. A string that fails to parse does not stop the
+// chain of setters: the failure is kept with the field and returned by
+// `build()` as the `Parse` variant of the builder's error, naming the field.
+// Setting the field again, by any setter, or clearing it drops the failure.
+
+use derive_builder::Builder;
+use std::time::Duration;
+
+fn parse_millis(s: &str) -> Result<Duration, std::num::ParseIntError> {
+    s.parse().map(Duration::from_millis)
+}
+
+#[derive(Builder, Debug)]
+pub struct Listener {
+    #[builder(try_setter, parse_with = "str::parse")]
+    port: u16,
+    #[builder(parse_with = parse_millis)]
+    timeout: Option<Duration>,
+}
+
+#[derive(Builder, Debug)]
+#[builder(pattern = "owned")]
+pub struct Retry {
+    #[builder(try_setter, parse_with = "str::parse")]
+    attempts: u8,
+}
+
+fn main() {
+    let mut builder = Listener::builder();
+    assert!(builder.try_port(70000u64).is_err());
+    let listener = builder.try_port(8080u64).unwrap().build().unwrap();
+    assert_eq!(listener.port, 8080);
+    assert_eq!(listener.timeout, None);
+
+    let listener = Listener::builder()
+        .parse_port("80")
+        .parse_timeout("1500")
+        .build()
+        .unwrap();
+    assert_eq!(listener.port, 80);
+    assert_eq!(listener.timeout, Some(Duration::from_millis(1500)));
+
+    let listener = Listener::builder()
+        .parse_port("abc")
+        .parse_port("80")
+        .build()
+        .unwrap();
+    assert_eq!(listener.port, 80);
+
+    let err = Listener::builder()
+        .parse_port("eighty")
+        .parse_timeout("soon")
+        .build()
+        .unwrap_err();
+    assert_eq!(err.field_name(), Some("port"));
+    assert_eq!(
+        err,
+        ListenerBuilderError::Parse {
+            field: "port",
+            message: "invalid digit found in string".to_owned(),
+        }
+    );
+    assert_eq!(
+        err.to_string(),
+        "port: invalid value: invalid digit found in string"
+    );
+
+    let err = Listener::builder()
+        .parse_port("eighty")
+        .parse_timeout("soon")
+        .port(80)
+        .build()
+        .unwrap_err();
+    assert_eq!(err.field_name(), Some("timeout"));
+
+    let retry = Retry::builder()
+        .parse_attempts("many")
+        .try_attempts(3i64)
+        .unwrap()
+        .build()
+        .unwrap();
+    assert_eq!(retry.attempts, 3);
+    let retry = Retry::builder().try_attempts(3i64).unwrap().build().unwrap();
+    assert_eq!(retry.attempts, 3);
+    let err = Retry::builder().parse_attempts("-1").build().unwrap_err();
+    assert_eq!(err.field_name(), Some("attempts"));
+}
@@ -0,0 +1,128 @@
+// Fields can be put in a group with #[builder(group = "...")], along with a
+// rule for how many of them may be set: `exactly_one`, `at_least_one` or
+// `at_most_one`. The rule only needs to be given on one field of the group.
+// Fields in a group have to be optional or have a default, since leaving some
+// of them unset is the point.
+//
+// build() checks the rule and reports a breach through the `Group` variant of
+// the builder's error, naming the group and the fields that were set. Those
+// are none of them when at least one was needed.
+//
+// On a typestate builder every group is tracked in the builder's type instead,
+// so that breaking the rule does not compile.
+
+use derive_builder::Builder;
+
+#[derive(Builder, Debug)]
+pub struct Login {
+    user: String,
+    #[builder(group = "auth", exactly_one)]
+    password: Option<String>,
+    #[builder(group = "auth")]
+    token: Option<String>,
+    #[builder(group = "output", at_most_one, default)]
+    quiet: bool,
+    #[builder(group = "output", default)]
+    verbose: bool,
+}
+
+#[derive(Builder, Debug)]
+#[builder(typestate)]
+pub struct Upload {
+    #[builder(group = "source", exactly_one)]
+    path: Option<String>,
+    #[builder(group = "source")]
+    bytes: Option<Vec<u8>>,
+    #[builder(group = "tags", at_least_one, default)]
+    tag: String,
+    #[builder(group = "tags", default)]
+    label: String,
+    #[builder(group = "log", at_most_one)]
+    log_file: Option<String>,
+    #[builder(group = "log")]
+    log_stderr: Option<bool>,
+}
+
+#[derive(Builder, Debug)]
+#[builder(typestate)]
+pub struct Session {
+    group_auth: String,
+    #[builder(group = "auth", exactly_one)]
+    password: Option<String>,
+    #[builder(group = "auth")]
+    token: Option<String>,
+}
+
+fn main() {
+    let login = Login::builder()
+        .user("root".to_owned())
+        .token("secret".to_owned())
+        .verbose(true)
+        .build()
+        .unwrap();
+    assert_eq!(login.password, None);
+    assert_eq!(login.token.as_deref(), Some("secret"));
+    assert!(login.verbose && !login.quiet);
+
+    let err = Login::builder().user("root".to_owned()).build().unwrap_err();
+    assert_eq!(
+        err,
+        LoginBuilderError::Group {
+            group: "auth",
+            fields: vec![],
+        }
+    );
+    assert_eq!(err.to_string(), "one of the fields in group auth must be set");
+    assert_eq!(err.field_name(), None);
+
+    let err = Login::builder()
+        .user("root".to_owned())
+        .password("hunter2".to_owned())
+        .token("secret".to_owned())
+        .build()
+        .unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "only one of the fields in group auth may be set, got password, token"
+    );
+
+    let err = Login::builder()
+        .user("root".to_owned())
+        .password("hunter2".to_owned())
+        .quiet(true)
+        .verbose(true)
+        .build()
+        .unwrap_err();
+    assert_eq!(
+        err,
+        LoginBuilderError::Group {
+            group: "output",
+            fields: vec!["quiet", "verbose"],
+        }
+    );
+
+    let upload = Upload::builder()
+        .tag("nightly".to_owned())
+        .path("build.tar".to_owned())
+        .label("ci".to_owned())
+        .build();
+    assert_eq!(upload.path.as_deref(), Some("build.tar"));
+    assert_eq!(upload.bytes, None);
+    assert_eq!((upload.tag.as_str(), upload.label.as_str()), ("nightly", "ci"));
+    assert_eq!(upload.log_file, None);
+
+    let upload = Upload::builder()
+        .bytes(vec![1, 2, 3])
+        .log_stderr(true)
+        .label("manual".to_owned())
+        .build();
+    assert_eq!(upload.bytes, Some(vec![1, 2, 3]));
+    assert_eq!(upload.log_stderr, Some(true));
+
+    let session = Session::builder()
+        .token("secret".to_owned())
+        .group_auth("admins".to_owned())
+        .build();
+    assert_eq!(session.group_auth, "admins");
+    assert_eq!(session.token.as_deref(), Some("secret"));
+}
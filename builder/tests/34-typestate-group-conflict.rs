@@ -0,0 +1,20 @@
+// Setting a second field of an `exactly_one` group on a typestate builder is a
+// compile error, as the group's setters are gone once one of them is called.
+
+use derive_builder::Builder;
+
+#[derive(Builder)]
+#[builder(typestate)]
+pub struct Login {
+    #[builder(group = "auth", exactly_one)]
+    password: Option<String>,
+    #[builder(group = "auth")]
+    token: Option<String>,
+}
+
+fn main() {
+    let _login = Login::builder()
+        .password("hunter2".to_owned())
+        .token("secret".to_owned())
+        .build();
+}
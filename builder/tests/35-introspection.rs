@@ -0,0 +1,119 @@
+// Builders marked #[builder(introspect)] can tell what is still missing
+// before build() is called: `missing_fields()` lists the required fields that
+// are not set yet, in declaration order, and `is_<field>_set()` tells whether
+// a single field has been set. Collection fields have no `is_<field>_set()`,
+// since an empty collection is a perfectly good value.
+//
+// `clear_<field>()` unsets a field again, along with any string that failed
+// to parse into it, and `reset()` starts over with an empty builder. Both
+// follow the builder's pattern like the setters do.
+//
+// A sub-builder field counts as missing while its own builder is missing any
+// field, so the inner struct needs #[builder(introspect)] too. A group that
+// needs one of its fields set is listed by its name after the fields while
+// none of them are. Strings that failed to parse and groups with too many
+// fields set are only reported by build().
+//
+// Builders without the attribute have none of these methods, leaving their
+// names free for fields.
+
+use derive_builder::Builder;
+
+#[derive(Builder, Debug)]
+#[builder(introspect)]
+pub struct Tls {
+    cert_path: String,
+    #[builder(default)]
+    verify: bool,
+}
+
+#[derive(Builder, Debug)]
+#[builder(introspect)]
+pub struct Server {
+    host: String,
+    port: u16,
+    #[builder(default = "30")]
+    timeout: u64,
+    name: Option<String>,
+    #[builder(each = "alias")]
+    aliases: Vec<String>,
+    #[builder(sub_builder)]
+    tls: Tls,
+}
+
+#[derive(Builder, Debug)]
+#[builder(pattern = "owned", introspect)]
+pub struct Job {
+    command: String,
+    #[builder(parse_with = "str::parse")]
+    retries: Option<u32>,
+}
+
+#[derive(Builder, Debug)]
+#[builder(introspect)]
+pub struct Login {
+    user: String,
+    #[builder(group = "auth", exactly_one)]
+    password: Option<String>,
+    #[builder(group = "auth")]
+    token: Option<String>,
+}
+
+#[derive(Builder, Debug)]
+pub struct Counter {
+    reset: bool,
+}
+
+fn main() {
+    let mut builder = Server::builder();
+    assert_eq!(builder.missing_fields(), ["host", "port", "tls"]);
+    assert!(!builder.is_host_set());
+
+    builder.host("localhost".to_owned()).timeout(5).alias("lo".to_owned());
+    assert!(builder.is_host_set());
+    assert!(builder.is_timeout_set());
+    assert!(!builder.is_name_set());
+    assert!(!builder.is_tls_set());
+    assert_eq!(builder.missing_fields(), ["port", "tls"]);
+
+    builder.tls().cert_path("server.pem".to_owned());
+    builder.port(443);
+    assert!(builder.is_tls_set());
+    assert!(builder.missing_fields().is_empty());
+
+    builder.clear_port().clear_timeout();
+    assert!(!builder.is_port_set());
+    assert_eq!(builder.missing_fields(), ["port"]);
+    let server = builder.port(8443).build().unwrap();
+    assert_eq!(server.timeout, 30);
+    assert_eq!(server.aliases, ["lo"]);
+
+    builder.reset();
+    assert_eq!(builder.missing_fields(), ["host", "port", "tls"]);
+    builder.clear_aliases();
+
+    let job = Job::builder().command("make".to_owned()).retries(3);
+    assert!(job.is_retries_set());
+    let job = job.clear_retries();
+    assert!(job.missing_fields().is_empty());
+    let job = job.build().unwrap();
+    assert_eq!(job.retries, None);
+
+    let job = Job::builder().command("make".to_owned()).reset();
+    assert_eq!(job.missing_fields(), ["command"]);
+    let job = Job::builder()
+        .command("make".to_owned())
+        .parse_retries("many")
+        .clear_retries()
+        .build()
+        .unwrap();
+    assert_eq!(job.retries, None);
+
+    let mut login = Login::builder();
+    assert_eq!(login.missing_fields(), ["user", "auth"]);
+    login.user("root".to_owned()).token("secret".to_owned());
+    assert!(login.missing_fields().is_empty());
+
+    let counter = Counter::builder().reset(true).build().unwrap();
+    assert!(counter.reset);
+}
@@ -0,0 +1,75 @@
+// Fields behind #[cfg] only exist in some builds, and so do their builder
+// members, setters, checks in build() and error variants.
+//
+// The compiler removes fields whose cfg does not hold before the derive sees
+// them at all, which is what leaves the builder without them. The `cfg`s of
+// the fields that are left, including those added by a #[cfg_attr], always
+// hold; they are repeated on what is generated for the field all the same, so
+// that it reads like the field. Other attributes a #[cfg_attr] adds are not.
+//
+// Here `any()` is a cfg that never holds and `all()` one that always does, so
+// the fields under `cfg(any())` can name types that do not even exist. This
+// only checks that fields of every kind keep working with cfgs on them.
+
+use derive_builder::Builder;
+
+#[derive(Builder, Debug, Clone)]
+#[builder(to_builder, merge, introspect)]
+pub struct Server {
+    host: String,
+    #[cfg(any())]
+    tls: missing::TlsConfig,
+    #[cfg(any())]
+    #[builder(each = "cipher", env = "SERVER_CIPHERS")]
+    ciphers: Vec<missing::Cipher>,
+    #[cfg_attr(all(), cfg(any()))]
+    #[builder(default, group = "limits")]
+    max_connections: missing::Limit,
+    #[builder(group = "limits", at_most_one)]
+    rate_limit: Option<u32>,
+    #[cfg(all())]
+    #[builder(default = "8080")]
+    port: u16,
+    #[cfg_attr(all(), allow(unused))]
+    backlog: Option<u32>,
+    #[cfg(any())]
+    #[builder(compute = "missing::compute()")]
+    derived: missing::Derived,
+}
+
+#[derive(Builder, Debug)]
+#[builder(typestate)]
+pub struct Client {
+    url: String,
+    #[cfg(any())]
+    proxy: Option<missing::Proxy>,
+    #[cfg(all())]
+    retries: Option<u32>,
+    #[cfg(all())]
+    token: String,
+    #[cfg_attr(all(), cfg(all()))]
+    region: String,
+}
+
+fn main() {
+    let mut builder = Server::builder();
+    assert_eq!(builder.missing_fields(), ["host"]);
+    let server = builder.host("localhost".to_owned()).build().unwrap();
+    assert_eq!(server.port, 8080);
+    assert_eq!(server.backlog, None);
+    assert_eq!(server.rate_limit, None);
+
+    let mut other = Server::builder();
+    other.rate_limit(100);
+    let server = server.to_builder().merge(other).build().unwrap();
+    assert_eq!(server.rate_limit, Some(100));
+
+    let client = Client::builder()
+        .url("https://example.com".to_owned())
+        .retries(3)
+        .token("secret".to_owned())
+        .region("eu".to_owned())
+        .build();
+    assert_eq!(client.retries, Some(3));
+    assert_eq!((client.token.as_str(), client.region.as_str()), ("secret", "eu"));
+}
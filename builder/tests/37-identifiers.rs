@@ -0,0 +1,81 @@
+// Names given to generated items, such as `each = "..."` or
+// `setter(name = "...")`, may be keywords: they are used as raw identifiers,
+// so `each = "match"` gives a setter called `r#match`. Raw identifiers can
+// also be written out, and fields that are raw identifiers themselves get
+// setters of the same name and are reported without the `r#`.
+//
+// The builder itself can be renamed with `name = "..."`, which also renames
+// its error to `<name>Error`, along with `build()` through
+// `build_fn = "..."` and the struct's `builder()` through
+// `constructor = "..."`.
+
+use derive_builder::Builder;
+
+#[derive(Builder, Debug)]
+#[builder(introspect)]
+pub struct Rule {
+    r#type: String,
+    #[builder(each = "match")]
+    matches: Vec<String>,
+    #[builder(each = "r#loop")]
+    loops: Vec<u32>,
+    #[builder(setter(name = "fn"))]
+    callback: Option<String>,
+}
+
+#[derive(Builder, Debug)]
+#[builder(name = "ServerConfigurator", build_fn = "finish", constructor = "configure")]
+pub struct Server {
+    host: String,
+    port: Option<u16>,
+}
+
+#[derive(Builder, Debug)]
+#[builder(typestate, name = "Connector", build_fn = "connect", constructor = "dial")]
+pub struct Connection {
+    url: String,
+}
+
+#[derive(Builder, Debug)]
+pub enum Shape {
+    #[builder(name = "CircleMaker", constructor = "circle")]
+    Circle { radius: f64 },
+    Square { side: f64 },
+}
+
+fn main() {
+    let rule = Rule::builder()
+        .r#type("deny".to_owned())
+        .r#match("*.rs".to_owned())
+        .r#loop(3)
+        .r#fn("log".to_owned())
+        .build()
+        .unwrap();
+    assert_eq!(rule.r#type, "deny");
+    assert_eq!(rule.matches, ["*.rs"]);
+    assert_eq!(rule.loops, [3]);
+    assert_eq!(rule.callback.as_deref(), Some("log"));
+
+    let err = Rule::builder().build().unwrap_err();
+    assert_eq!(err, RuleBuilderError::MissingType);
+    assert_eq!(err.field_name(), Some("type"));
+    assert_eq!(err.to_string(), "type is not set");
+    assert_eq!(Rule::builder().missing_fields(), ["type"]);
+
+    let mut configurator: ServerConfigurator = Server::configure();
+    let server = configurator.host("localhost".to_owned()).finish().unwrap();
+    assert_eq!(server.host, "localhost");
+    let err: ServerConfiguratorError = Server::configure().finish().unwrap_err();
+    assert_eq!(err, ServerConfiguratorError::MissingHost);
+
+    let connection = Connection::dial().url("tcp://db".to_owned()).connect();
+    assert_eq!(connection.url, "tcp://db");
+    let _: fn() -> Connector<()> = Connection::dial;
+
+    let circle = Shape::circle().radius(1.0).build().unwrap();
+    assert!(matches!(circle, Shape::Circle { radius } if radius == 1.0));
+    let _: CircleMaker = Shape::circle();
+    let _: CircleMakerError = CircleMakerError::MissingRadius;
+    let square = Shape::square_builder().side(2.0).build().unwrap();
+    assert!(matches!(square, Shape::Square { side } if side == 2.0));
+}
@@ -0,0 +1,20 @@
+// Names that cannot be identifiers, even raw ones, are reported at the string
+// that gives them rather than making the derive panic. The builder is still
+// generated without them.
+
+use derive_builder::Builder;
+
+#[derive(Builder)]
+#[builder(name = "Server Builder", build_fn = "self")]
+pub struct Server {
+    #[builder(each = "add alias")]
+    aliases: Vec<String>,
+    #[builder(setter(name = "3d"))]
+    depth: u32,
+    #[builder(each = "r#crate")]
+    crates: Vec<String>,
+}
+
+fn main() {
+    let _ = Server::builder().build();
+}
@@ -0,0 +1,26 @@
+// A collection given by its item type with #[builder(item = ...)] only needs
+// to implement Default and Extend, so there is no telling what a builder
+// holds in it and #[builder(merge)] cannot combine two of them.
+
+use derive_builder::Builder;
+
+#[derive(Clone, Default)]
+pub struct Path(String);
+
+impl Extend<&'static str> for Path {
+    fn extend<I: IntoIterator<Item = &'static str>>(&mut self, iter: I) {
+        for segment in iter {
+            self.0.push('/');
+            self.0.push_str(segment);
+        }
+    }
+}
+
+#[derive(Builder)]
+#[builder(merge)]
+pub struct Request {
+    #[builder(each = "segment", item = &'static str)]
+    path: Path,
+}
+
+fn main() {}
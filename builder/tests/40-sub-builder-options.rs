@@ -0,0 +1,20 @@
+// A sub-builder field is set through the inner builder its accessor returns,
+// so the options shaping a setter have nothing to apply to and are rejected
+// rather than ignored.
+
+use derive_builder::Builder;
+
+#[derive(Builder)]
+pub struct Timeouts {
+    connect_ms: u32,
+}
+
+#[derive(Builder)]
+pub struct Pool {
+    #[builder(sub_builder, try_setter)]
+    timeouts: Timeouts,
+    #[builder(sub_builder, parse_with = "str::parse", setter(into))]
+    fallback: Timeouts,
+}
+
+fn main() {}
@@ -0,0 +1,20 @@
+// A typestate builder tracks its fields in its type, which a sub_builder field
+// has no way to follow, so it cannot be the inner builder of another one.
+// Among the errors about the inner builder lacking what the outer one uses is
+// one saying as much.
+
+use derive_builder::Builder;
+
+#[derive(Builder)]
+#[builder(typestate)]
+pub struct Credentials {
+    user: String,
+}
+
+#[derive(Builder)]
+pub struct Client {
+    #[builder(sub_builder)]
+    credentials: Credentials,
+}
+
+fn main() {}
@@ -12,4 +12,36 @@ fn tests() {
     // stderr for this, I made my errors more specific!
     t.compile_fail("tests/08-unrecognized-attribute.rs");
     t.pass("tests/09-redefined-prelude-types.rs");
+    t.pass("tests/10-typestate.rs");
+    t.compile_fail("tests/11-typestate-missing-field.rs");
+    t.pass("tests/12-generics.rs");
+    t.pass("tests/13-builder-error.rs");
+    t.pass("tests/14-default-values.rs");
+    t.pass("tests/15-validate.rs");
+    t.pass("tests/16-setter-options.rs");
+    t.pass("tests/17-builder-pattern.rs");
+    t.pass("tests/18-enum-variants.rs");
+    t.pass("tests/19-tuple-structs.rs");
+    t.pass("tests/20-collections.rs");
+    t.pass("tests/21-qualified-paths.rs");
+    t.pass("tests/22-foreign-attributes.rs");
+    t.compile_fail("tests/23-multiple-errors.rs");
+    t.pass("tests/24-to-builder.rs");
+    t.pass("tests/25-merge.rs");
+    t.pass("tests/26-env.rs");
+    t.pass("tests/27-skip-compute.rs");
+    t.compile_fail("tests/28-skip-has-no-setter.rs");
+    t.pass("tests/29-visibility.rs");
+    t.pass("tests/30-sub-builder.rs");
+    t.pass("tests/31-no-std.rs");
+    t.pass("tests/32-fallible-setters.rs");
+    t.pass("tests/33-field-groups.rs");
+    t.compile_fail("tests/34-typestate-group-conflict.rs");
+    t.pass("tests/35-introspection.rs");
+    t.pass("tests/36-cfg-fields.rs");
+    t.pass("tests/37-identifiers.rs");
+    t.compile_fail("tests/38-invalid-identifiers.rs");
+    t.compile_fail("tests/39-merge-item-collection.rs");
+    t.compile_fail("tests/40-sub-builder-options.rs");
+    t.compile_fail("tests/41-typestate-sub-builder.rs");
 }